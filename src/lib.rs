@@ -4,7 +4,11 @@ extern crate log;
 use time;
 
 use std::{fs, io, path};
+use std::io::Write;
 use crate::sat::*;
+use crate::sat::backbone::BackboneRes;
+use crate::sat::formula::{Var, VarMap};
+use crate::sat::mus::MusRes;
 use crate::sat::minisat::budget::Budget;
 
 pub mod sat;
@@ -14,23 +18,72 @@ pub(crate) mod util;
 pub enum SolverOptions {
     Core(minisat::CoreSettings),
     Simp(minisat::SimpSettings),
+    Portfolio(portfolio::PortfolioSettings),
+}
+
+pub enum Mode {
+    Solve,
+    Cube(path::PathBuf, minisat::CubeSettings), // Write the cubes of the formula to a file (iCNF).
+    Conquer(path::PathBuf), // Solve the formula under every cube from a file.
+    Incremental, // The input is in the iCNF format, answer every query of it.
+    Backbone, // Print the literals true in every model.
+    Mus, // Print a minimal unsatisfiable subset of the groups (of the clauses of a plain CNF).
 }
 
 pub struct MainOptions {
+    pub mode: Mode,
     pub strict: bool,
     pub pre: bool,
     pub solve: bool,
     pub in_path: path::PathBuf,
     pub out_path: Option<path::PathBuf>,
     pub dimacs_path: Option<path::PathBuf>,
+    pub stats_json: Option<path::PathBuf>, // Write the statistics to this file (JSON).
+    pub checkpoint: Option<(path::PathBuf, u64)>, // Save the solver to this file every number of conflicts.
+    pub resume: Option<path::PathBuf>, // Continue from this checkpoint instead of parsing the input.
+}
+
+
+// Seconds spent in the phases of the solving.
+#[derive(Clone, Copy, Default)]
+struct Phases {
+    parse: f64,
+    simplify: f64,
+    search: f64,
 }
 
 
 pub fn solve(main_opts: MainOptions, solver_opts: SolverOptions) -> io::Result<()> {
+    if let Mode::Solve = main_opts.mode {
+    } else {
+        // Cubes and assumptions refer to the variables of the input, so they must not be eliminated:
+        let core_opts =
+            match solver_opts {
+                SolverOptions::Core(opts) => opts,
+                SolverOptions::Simp(opts) => opts.core,
+                SolverOptions::Portfolio(opts) => opts.base.core,
+            };
+
+        let solver = minisat::CoreSolver::new(core_opts);
+        return match main_opts.mode {
+            Mode::Cube(ref path, ref settings) => cube_with(solver, &main_opts, path, settings),
+            Mode::Conquer(ref path) => conquer_with(solver, &main_opts, path),
+            Mode::Incremental => incremental_with(solver, &main_opts),
+            Mode::Backbone => backbone_with(solver, &main_opts),
+            Mode::Mus => mus_with(solver, &main_opts),
+            Mode::Solve => unreachable!(),
+        };
+    }
+
+    let checkpoints = main_opts.checkpoint.is_some() || main_opts.resume.is_some();
     match solver_opts {
         SolverOptions::Core(opts) => {
             let solver = minisat::CoreSolver::new(opts);
-            solve_with(solver, main_opts)
+            if checkpoints {
+                checkpoint_with(solver, main_opts)
+            } else {
+                solve_with(solver, main_opts)
+            }
         }
 
         SolverOptions::Simp(opts) => {
@@ -38,6 +91,15 @@ pub fn solve(main_opts: MainOptions, solver_opts: SolverOptions) -> io::Result<(
             if !main_opts.pre {
                 solver.preprocess(&Budget::new());
             }
+            if checkpoints {
+                checkpoint_with(solver, main_opts)
+            } else {
+                solve_with(solver, main_opts)
+            }
+        }
+
+        SolverOptions::Portfolio(opts) => {
+            let solver = portfolio::Portfolio::new(opts);
             solve_with(solver, main_opts)
         }
     }
@@ -51,13 +113,14 @@ pub fn solve_with<S: Solver>(mut solver: S, options: MainOptions) -> io::Result<
     let initial_time = time::precise_time_s();
     let backward_subst = dimacs::parse_file(&options.in_path, &mut solver, options.strict)?;
     let parse_end_time = time::precise_time_s();
+    let mut phases = Phases::default();
 
     info!("|  Number of variables:  {:12}                                         |", solver.n_vars());
     info!("|  Number of clauses:    {:12}                                         |", solver.n_clauses());
 
     {
-        let parse_time = parse_end_time - initial_time;
-        info!("|  Parse time:           {:12.2} s                                       |", parse_time);
+        phases.parse = parse_end_time - initial_time;
+        info!("|  Parse time:           {:12.2} s                                       |", phases.parse);
     }
 
     let mut budget = Budget::new();
@@ -66,8 +129,8 @@ pub fn solve_with<S: Solver>(mut solver: S, options: MainOptions) -> io::Result<
     let elim_res = solver.preprocess(&budget);
 
     {
-        let simplify_time = time::precise_time_s() - parse_end_time;
-        info!("|  Simplification time:  {:12.2} s                                       |", simplify_time);
+        phases.simplify = time::precise_time_s() - parse_end_time;
+        info!("|  Simplification time:  {:12.2} s                                       |", phases.simplify);
     }
 
     info!("|                                                                             |");
@@ -79,7 +142,10 @@ pub fn solve_with<S: Solver>(mut solver: S, options: MainOptions) -> io::Result<
     } else {
         let result =
             if options.solve {
-                solver.solve_limited(&budget, &[])
+                let search_start_time = time::precise_time_s();
+                let result = solver.solve_limited(&budget, &[]);
+                phases.search = time::precise_time_s() - search_start_time;
+                result
             } else {
                 info!("===============================================================================");
                 SolveRes::Interrupted(0.0, solver)
@@ -95,36 +161,367 @@ pub fn solve_with<S: Solver>(mut solver: S, options: MainOptions) -> io::Result<
         result
     };
 
+    print_result(result, &options, &backward_subst, initial_time, phases)
+}
+
+
+// Same as 'solve_with', but the solver is saved to a checkpoint file periodically, or restored from
+// one instead of parsing the input. Checkpoints start with the DIMACS numbering of the variables.
+pub fn checkpoint_with<S: minisat::Checkpoint>(mut solver: S, options: MainOptions) -> io::Result<()> {
+    let initial_time = time::precise_time_s();
+    let mut phases = Phases::default();
+    let mut budget = Budget::new();
+    budget.off();
+
+    let (backward_subst, ok) =
+        match options.resume {
+            Some(ref path) => {
+                let mut r = minisat::CheckpointReader::new(io::BufReader::new(fs::File::open(path)?))?;
+                let backward_subst = r.var_map()?;
+                solver.resume(&mut r)?;
+                info!("|  Resumed from:         {:>12}                                         |", path.display());
+                phases.parse = time::precise_time_s() - initial_time;
+                (backward_subst, true)
+            }
+
+            None => {
+                let backward_subst = dimacs::parse_file(&options.in_path, &mut solver, options.strict)?;
+                let parse_end_time = time::precise_time_s();
+                phases.parse = parse_end_time - initial_time;
+                let ok = solver.preprocess(&budget);
+                phases.simplify = time::precise_time_s() - parse_end_time;
+                (backward_subst, ok)
+            }
+        };
+
+    info!("|  Number of variables:  {:12}                                         |", solver.n_vars());
+    info!("|  Number of clauses:    {:12}                                         |", solver.n_clauses());
+    info!("|                                                                             |");
+
+    let result =
+        if !ok {
+            SolveRes::UnSAT(Stats::default())
+        } else if !options.solve {
+            SolveRes::Interrupted(0.0, solver)
+        } else {
+            loop {
+                let search_start_time = time::precise_time_s();
+                let (ref path, every) =
+                    match options.checkpoint {
+                        Some(ref checkpoint) => checkpoint.clone(),
+                        None => {
+                            let result = solver.solve_limited(&budget, &[]);
+                            phases.search += time::precise_time_s() - search_start_time;
+                            break result;
+                        }
+                    };
+
+                budget.set_conflict_limit(solver.stats().conflicts + every);
+                let result = solver.solve_limited(&budget, &[]);
+                phases.search += time::precise_time_s() - search_start_time;
+                match result {
+                    SolveRes::Interrupted(_, s) => {
+                        // Written aside first, so that an interrupted write keeps the last checkpoint:
+                        let tmp_path = path.with_extension("tmp");
+                        let mut w = minisat::CheckpointWriter::new(io::BufWriter::new(fs::File::create(&tmp_path)?))?;
+                        w.var_map(&backward_subst)?;
+                        s.checkpoint(&mut w)?;
+                        w.finish()?;
+                        fs::rename(&tmp_path, path)?;
+                        info!("Checkpoint written after {} conflicts", s.stats().conflicts);
+                        solver = s;
+                    }
+
+                    res => break res,
+                }
+            }
+        };
+
+    print_result(result, &options, &backward_subst, initial_time, phases)
+}
+
+
+// Splits the formula into cubes and writes them with the formula to 'cubes_path' in the iCNF format.
+pub fn cube_with(mut solver: minisat::CoreSolver, options: &MainOptions, cubes_path: &path::Path, settings: &minisat::CubeSettings) -> io::Result<()> {
+    let initial_time = time::precise_time_s();
+    let backward_subst = dimacs::parse_file(&options.in_path, &mut solver, options.strict)?;
+
+    info!("|  Number of variables:  {:12}                                         |", solver.n_vars());
+    info!("|  Number of clauses:    {:12}                                         |", solver.n_clauses());
+
+    let search_start_time = time::precise_time_s();
+    let cubes =
+        if solver.preprocess(&Budget::new()) {
+            solver.cube(settings)
+        } else {
+            Vec::new()
+        };
+    let phases = Phases { search: time::precise_time_s() - search_start_time, ..Phases::default() };
+
+    {
+        let cube_time = time::precise_time_s() - initial_time;
+        info!("|  Number of cubes:      {:12}                                         |", cubes.len());
+        info!("|  Cubing time:          {:12.2} s                                       |", cube_time);
+    }
+
+    dimacs::write_icnf_file(&options.in_path, io::BufWriter::new(fs::File::create(cubes_path)?), &cubes, &backward_subst)?;
+
+    // Every cube not refuted by lookahead is left to the conquer phase:
+    let result =
+        if cubes.is_empty() {
+            SolveRes::UnSAT(solver.stats())
+        } else {
+            SolveRes::Interrupted(0.0, solver)
+        };
+    print_result(result, options, &backward_subst, initial_time, phases)
+}
+
+
+// Solves the formula under every cube from 'cubes_path', keeping learnt clauses between the cubes,
+// until a model is found.
+pub fn conquer_with<S: IncrementalSolver>(mut solver: S, options: &MainOptions, cubes_path: &path::Path) -> io::Result<()> {
+    let initial_time = time::precise_time_s();
+    let backward_subst = dimacs::parse_file(&options.in_path, &mut solver, options.strict)?;
+    let cubes = dimacs::parse_cubes_file(cubes_path, &backward_subst)?;
+
+    info!("|  Number of variables:  {:12}                                         |", solver.n_vars());
+    info!("|  Number of clauses:    {:12}                                         |", solver.n_clauses());
+    info!("|  Number of cubes:      {:12}                                         |", cubes.len());
+
+    let mut budget = Budget::new();
+    budget.off();
+
+    // The cubes cover all models, so the formula is unsatisfiable if all of them are:
+    let search_start_time = time::precise_time_s();
+    let mut outcome = IncrementalRes::UnSat(Vec::new());
+    if solver.preprocess(&budget) {
+        for (i, cube) in cubes.iter().enumerate() {
+            match solver.solve_incremental(&budget, cube) {
+                IncrementalRes::UnSat(ref failed) if !failed.is_empty() => {
+                    trace!("Cube {} of {} is unsatisfiable", i + 1, cubes.len());
+                }
+
+                res => {
+                    outcome = res;
+                    break;
+                }
+            }
+        }
+    }
+
+    let phases = Phases { search: time::precise_time_s() - search_start_time, ..Phases::default() };
+
+    let result =
+        match outcome {
+            IncrementalRes::UnSat(_) => SolveRes::UnSAT(solver.stats()),
+            IncrementalRes::Sat(model) => SolveRes::SAT(model, solver.stats()),
+            IncrementalRes::Interrupted(c) => SolveRes::Interrupted(c, solver),
+        };
+    print_result(result, options, &backward_subst, initial_time, phases)
+}
+
+
+// Reads an iCNF input and solves every query under its assumptions, with the clauses given before
+// it. Prints one result per query, followed by the failed assumptions of unsatisfiable queries.
+pub fn incremental_with<S: IncrementalSolver>(mut solver: S, options: &MainOptions) -> io::Result<()> {
+    let initial_time = time::precise_time_s();
+
+    let mut out =
+        match options.out_path {
+            Some(ref path) => Some(io::BufWriter::new(fs::File::create(path)?)),
+            None => None,
+        };
+
+    let mut budget = Budget::new();
+    budget.off();
+
+    let mut queries = 0;
+    dimacs::parse_icnf_file(&options.in_path, &mut solver, |solver, assumptions, backward_subst| {
+        queries += 1;
+        let result = solver.solve_incremental(&budget, assumptions);
+        match result {
+            IncrementalRes::UnSat(ref failed) => {
+                println!("UNSATISFIABLE");
+                if !failed.is_empty() {
+                    dimacs::write_lits(io::stdout(), "f ", failed, backward_subst)?;
+                }
+            }
+
+            IncrementalRes::Interrupted(_) => {
+                println!("INDETERMINATE");
+            }
+
+            IncrementalRes::Sat(_) => {
+                println!("SATISFIABLE");
+            }
+        }
+
+        if let Some(ref mut out) = out {
+            dimacs::write_query_result(out, &result, backward_subst)?;
+        }
+        Ok(())
+    })?;
+
+    info!("|  Number of variables:  {:12}                                         |", solver.n_vars());
+    info!("|  Number of clauses:    {:12}                                         |", solver.n_clauses());
+    info!("|  Number of queries:    {:12}                                         |", queries);
+    report_stats(&solver.stats(), options, time::precise_time_s() - initial_time)
+}
+
+
+// Computes the backbone of the formula and prints it on a 'b' line, in the DIMACS numbering.
+pub fn backbone_with<S: IncrementalSolver>(mut solver: S, options: &MainOptions) -> io::Result<()> {
+    let initial_time = time::precise_time_s();
+    let backward_subst = dimacs::parse_file(&options.in_path, &mut solver, options.strict)?;
+
+    info!("|  Number of variables:  {:12}                                         |", solver.n_vars());
+    info!("|  Number of clauses:    {:12}                                         |", solver.n_clauses());
+
+    let mut budget = Budget::new();
+    budget.off();
+
+    // Preprocessed by the first solve, once the variables are frozen:
+    let vars: Vec<Var> = backward_subst.iter().map(|(v, _)| v).collect();
+    let result = backbone::backbone(&mut solver, &budget, &vars);
+
+    if let BackboneRes::Backbone(ref lits) = result {
+        info!("|  Backbone size:        {:12}                                         |", lits.len());
+    }
+    report_stats(&solver.stats(), options, time::precise_time_s() - initial_time)?;
+
+    let mut out =
+        match options.out_path {
+            Some(ref path) => Some(io::BufWriter::new(fs::File::create(path)?)),
+            None => None,
+        };
+
+    match result {
+        BackboneRes::UnSat => {
+            println!("UNSATISFIABLE");
+            if let Some(ref mut out) = out {
+                writeln!(out, "UNSAT")?;
+            }
+        }
+
+        BackboneRes::Interrupted => {
+            println!("INDETERMINATE");
+            if let Some(ref mut out) = out {
+                writeln!(out, "INDET")?;
+            }
+        }
+
+        BackboneRes::Backbone(ref lits) => {
+            println!("SATISFIABLE");
+            dimacs::write_lits(io::stdout(), "b ", lits, &backward_subst)?;
+            if let Some(ref mut out) = out {
+                writeln!(out, "SAT")?;
+                dimacs::write_lits(out, "", lits, &backward_subst)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+
+// Reads a group CNF and prints a minimal unsatisfiable subset of its groups on a 'v' line, numbered
+// as in the input.
+pub fn mus_with<S: IncrementalSolver>(mut solver: S, options: &MainOptions) -> io::Result<()> {
+    let initial_time = time::precise_time_s();
+    let gcnf = dimacs::parse_gcnf_file(&options.in_path, &mut solver, options.strict)?;
+
+    info!("|  Number of variables:  {:12}                                         |", solver.n_vars());
+    info!("|  Number of groups:     {:12}                                         |", gcnf.groups.len());
+
+    let mut budget = Budget::new();
+    budget.off();
+
+    let result = mus::mus(&mut solver, &budget, &gcnf.hard, &gcnf.groups);
+    if let MusRes::Mus(ref groups) = result {
+        info!("|  MUS size:             {:12}                                         |", groups.len());
+    }
+    report_stats(&solver.stats(), options, time::precise_time_s() - initial_time)?;
+
+    let mut out =
+        match options.out_path {
+            Some(ref path) => Some(io::BufWriter::new(fs::File::create(path)?)),
+            None => None,
+        };
+
+    match result {
+        MusRes::Sat => {
+            println!("SATISFIABLE");
+            if let Some(ref mut out) = out {
+                writeln!(out, "SAT")?;
+            }
+        }
+
+        MusRes::Interrupted => {
+            println!("INDETERMINATE");
+            if let Some(ref mut out) = out {
+                writeln!(out, "INDET")?;
+            }
+        }
+
+        MusRes::Mus(ref groups) => {
+            let line: String = groups.iter().map(|g| format!("{} ", g + 1)).collect();
+            println!("UNSATISFIABLE");
+            println!("v {}0", line);
+            if let Some(ref mut out) = out {
+                writeln!(out, "UNSAT")?;
+                writeln!(out, "{}0", line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+
+fn print_result<S: Solver>(result: SolveRes<S>, options: &MainOptions, backward_subst: &VarMap<i32>, initial_time: f64, phases: Phases) -> io::Result<()> {
     let cpu_time = time::precise_time_s() - initial_time;
-    let mem_used = util::mem_used_peak();
+    let mut stats =
+        match result {
+            SolveRes::UnSAT(ref stats) | SolveRes::SAT(_, ref stats) => stats.clone(),
+            SolveRes::Interrupted(_, ref s) => s.stats(),
+        };
+    stats.parse_time = phases.parse;
+    stats.simplify_time = phases.simplify;
+    stats.search_time = phases.search;
+    report_stats(&stats, options, cpu_time)?;
+
     match result {
-        SolveRes::UnSAT(ref stats) => {
-            print_stats(stats, cpu_time, mem_used);
+        SolveRes::UnSAT(_) => {
             println!("UNSATISFIABLE");
         }
 
-        SolveRes::Interrupted(_, ref s) => {
-            print_stats(&s.stats(), cpu_time, mem_used);
+        SolveRes::Interrupted(_, _) => {
             println!("INDETERMINATE");
         }
 
-        SolveRes::SAT(ref model, ref stats) => {
-            print_stats(stats, cpu_time, mem_used);
+        SolveRes::SAT(ref model, _) => {
             println!("SATISFIABLE");
             assert!(
-                dimacs::validate_model_file(&options.in_path, &backward_subst, &model)?,
+                dimacs::validate_model_file(&options.in_path, backward_subst, &model)?,
                 "SELF-CHECK FAILED"
             );
         }
     }
 
-    if let Some(path) = options.out_path {
-        dimacs::write_result(fs::File::create(path)?, result, &backward_subst)?;
+    if let Some(ref path) = options.out_path {
+        dimacs::write_result(fs::File::create(path)?, result, backward_subst)?;
     }
 
     Ok(())
 }
 
+fn report_stats(stats: &Stats, options: &MainOptions, cpu_time: f64) -> io::Result<()> {
+    let mem_used = util::mem_used_peak();
+    print_stats(stats, cpu_time, mem_used);
+    if let Some(ref path) = options.stats_json {
+        write_stats_json(io::BufWriter::new(fs::File::create(path)?), stats, cpu_time, mem_used)?;
+    }
+    Ok(())
+}
+
 fn print_stats(stats: &Stats, cpu_time: f64, mem_used: Option<usize>) {
     info!("restarts              : {:<12}", stats.restarts);
 
@@ -155,3 +552,53 @@ fn print_stats(stats: &Stats, cpu_time: f64, mem_used: Option<usize>) {
     info!("CPU time              : {} s", cpu_time);
     info!("");
 }
+
+fn write_stats_json<W: io::Write>(mut writer: W, stats: &Stats, cpu_time: f64, mem_used: Option<usize>) -> io::Result<()> {
+    let counters = [
+        ("solves", stats.solves),
+        ("restarts", stats.restarts),
+        ("decisions", stats.decisions),
+        ("rnd_decisions", stats.rnd_decisions),
+        ("conflicts", stats.conflicts),
+        ("propagations", stats.propagations),
+        ("conflict_literals", stats.tot_literals),
+        ("minimized_literals", stats.del_literals),
+        ("learnt_clauses", stats.learnts),
+        ("reductions", stats.reductions),
+        ("garbage_collections", stats.garbage_collections),
+        ("eliminated_vars", stats.eliminated_vars),
+        ("asymm_lits", stats.asymm_lits),
+        ("equiv_vars", stats.equiv_vars),
+        ("blocked_clauses", stats.blocked_clauses),
+        ("added_vars", stats.added_vars),
+        ("vivified_clauses", stats.vivified_clauses),
+        ("vivified_literals", stats.vivified_literals),
+        ("gate_vars", stats.gates),
+        ("chrono_backtracks", stats.chrono_backtracks),
+        ("blocked_restarts", stats.blocked_restarts),
+        ("mode_switches", stats.mode_switches),
+        ("rephases", stats.rephases),
+        ("walk_flips", stats.walk_flips),
+    ];
+    let reals = [
+        ("avg_lbd", stats.avg_lbd()),
+        ("avg_trail", stats.avg_trail()),
+        ("parse_time", stats.parse_time),
+        ("simplify_time", stats.simplify_time),
+        ("search_time", stats.search_time),
+        ("cpu_time", cpu_time),
+    ];
+
+    writeln!(writer, "{{")?;
+    for &(name, value) in counters.iter() {
+        writeln!(writer, "  \"{}\": {},", name, value)?;
+    }
+    for &(name, value) in reals.iter() {
+        writeln!(writer, "  \"{}\": {},", name, value)?;
+    }
+    match mem_used {
+        Some(mem_used) => writeln!(writer, "  \"memory_mb\": {}", mem_used as f64 / 1024.0)?,
+        None => writeln!(writer, "  \"memory_mb\": null")?,
+    }
+    writeln!(writer, "}}")
+}
@@ -7,7 +7,8 @@ use minisat_rust;
 
 use std::path;
 use std::io::Write;
-use minisat_rust::sat::minisat::{self, CCMinMode, PhaseSaving};
+use minisat_rust::sat::minisat::{self, Branching, CCMinMode, LocalSearchAlgorithm, PhaseSaving, RestartPolicy};
+use minisat_rust::sat::portfolio;
 
 
 fn main() {
@@ -25,6 +26,18 @@ fn main() {
         .arg(clap::Arg::with_name("solve").long("solve").help("Completely turn on/off solving after preprocessing"))
         .arg(clap::Arg::with_name("no-solve").long("no-solve").conflicts_with("solve"))
         .arg(clap::Arg::with_name("dimacs").long("dimacs").takes_value(true).requires("no-solve").help("If given, stop after preprocessing and write the result to this file"))
+        .arg(clap::Arg::with_name("cube").long("cube").takes_value(true).conflicts_with("threads").help("Split the formula into cubes by lookahead and write them to this file (iCNF)"))
+        .arg(clap::Arg::with_name("cube-depth").long("cube-depth").takes_value(true).requires("cube").help("Maximal number of decisions in a cube"))
+        .arg(clap::Arg::with_name("cube-free").long("cube-free").takes_value(true).requires("cube").help("Don't split formulas with less than this fraction of variables unassigned"))
+        .arg(clap::Arg::with_name("conquer").long("conquer").takes_value(true).conflicts_with("cube").conflicts_with("threads").help("Solve the formula under every cube from this file (iCNF)"))
+        .arg(clap::Arg::with_name("icnf").long("icnf").conflicts_with_all(&["cube", "conquer", "threads"]).help("The input is in the iCNF format, solve every query under its assumptions"))
+        .arg(clap::Arg::with_name("backbone").long("backbone").conflicts_with_all(&["cube", "conquer", "icnf", "threads"]).help("Compute the literals true in every model"))
+        .arg(clap::Arg::with_name("mus").long("mus").conflicts_with_all(&["cube", "conquer", "icnf", "threads", "backbone"]).help("Compute a minimal unsatisfiable subset of the groups of a GCNF (of the clauses of a CNF)"))
+        .arg(clap::Arg::with_name("stats-json").long("stats-json").takes_value(true).help("Write the solver statistics to this file (JSON)"))
+        .arg(clap::Arg::with_name("checkpoint").long("checkpoint").takes_value(true).conflicts_with_all(&["cube", "conquer", "icnf", "threads"]).help("Save the solver to this file periodically"))
+        .arg(clap::Arg::with_name("checkpoint-every").long("checkpoint-every").takes_value(true).requires("checkpoint").help("Number of conflicts between two checkpoints"))
+        .arg(clap::Arg::with_name("resume").long("resume").takes_value(true).conflicts_with_all(&["cube", "conquer", "icnf", "threads"]).help("Continue from a checkpoint of the same input and options (the input is still needed: it is only read to check the model)"))
+        .arg(clap::Arg::with_name("threads").long("threads").takes_value(true).help("Run a portfolio of differently configured solvers on this number of threads"))
         .arg(clap::Arg::with_name("input").required(true))
         .arg(clap::Arg::with_name("output").required(false))
 
@@ -34,21 +47,53 @@ fn main() {
         .arg(clap::Arg::with_name("rnd-seed").long("rnd-seed").takes_value(true).help("Used by the random variable selection"))
         .arg(clap::Arg::with_name("ccmin-mode").long("ccmin-mode").takes_value(true).possible_values(&ls012).help("Controls conflict clause minimization (0=none, 1=basic, 2=deep)"))
         .arg(clap::Arg::with_name("phase-saving").long("phase-saving").takes_value(true).possible_values(&ls012).help("Controls the level of phase saving (0=none, 1=limited, 2=full)"))
+        .arg(clap::Arg::with_name("branching").long("branching").takes_value(true).possible_values(&["vsids", "vmtf", "lrb", "chb"]).help("The branching heuristic"))
+        .arg(clap::Arg::with_name("stable-branching").long("stable-branching").takes_value(true).possible_values(&["vsids", "vmtf", "lrb", "chb"]).help("The branching heuristic of the stable mode"))
+        .arg(clap::Arg::with_name("target-phase").long("target-phase").help("Use target phases in the stable mode"))
+        .arg(clap::Arg::with_name("no-target-phase").long("no-target-phase").conflicts_with("target-phase"))
         .arg(clap::Arg::with_name("rnd-init").long("rnd-init").help("Randomize the initial activity"))
         .arg(clap::Arg::with_name("no-rnd-init").long("no-rnd-init").conflicts_with("rnd-init"))
         .arg(clap::Arg::with_name("luby").long("luby").help("Use the Luby restart sequence"))
         .arg(clap::Arg::with_name("no-luby").long("no-luby").conflicts_with("luby"))
+        .arg(clap::Arg::with_name("restarts").long("restarts").takes_value(true).possible_values(&["static", "glucose", "ema"]).help("Restart policy (static=Luby or geometric, glucose=LBD window, ema=LBD moving averages)"))
+        .arg(clap::Arg::with_name("lbd-margin").long("lbd-margin").takes_value(true).help("Dynamic restarts: restart if recent LBDs exceed the average by this factor"))
+        .arg(clap::Arg::with_name("restart-block").long("restart-block").takes_value(true).help("Dynamic restarts: block restarts if the trail exceeds its average by this factor"))
+        .arg(clap::Arg::with_name("no-restart-block").long("no-restart-block").conflicts_with("restart-block"))
+        .arg(clap::Arg::with_name("modes").long("modes").help("Alternate between the focused and the stable mode"))
+        .arg(clap::Arg::with_name("no-modes").long("no-modes").conflicts_with("modes"))
+        .arg(clap::Arg::with_name("rephase").long("rephase").help("Periodically reset saved phases (original, inverted, best, random)"))
+        .arg(clap::Arg::with_name("no-rephase").long("no-rephase").conflicts_with("rephase"))
+        .arg(clap::Arg::with_name("local-search").long("local-search").help("Only run local search, without CDCL (INDETERMINATE unless it finds a model)"))
+        .arg(clap::Arg::with_name("ls-algorithm").long("ls-algorithm").takes_value(true).possible_values(&["probsat", "walksat"]).help("The local search algorithm"))
+        .arg(clap::Arg::with_name("ls-flips").long("ls-flips").takes_value(true).help("The flip limit of the standalone local search"))
+        .arg(clap::Arg::with_name("walk").long("walk").requires("rephase").help("Seed saved phases with local search when rephasing"))
+        .arg(clap::Arg::with_name("no-walk").long("no-walk").conflicts_with("walk"))
         .arg(clap::Arg::with_name("rfirst").long("rfirst").takes_value(true).help("The base restart interval"))
         .arg(clap::Arg::with_name("rinc").long("rinc").takes_value(true).help("Restart interval increase factor"))
         .arg(clap::Arg::with_name("gc-frac").long("gc-frac").takes_value(true).help("The fraction of wasted memory allowed before a garbage collection is triggered"))
         .arg(clap::Arg::with_name("min-learnts").long("min-learnts").takes_value(true).help("Minimum learnt clause limit"))
         .arg(clap::Arg::with_name("rcheck").long("rcheck").help("Check if a clause is already implied. (costly)"))
         .arg(clap::Arg::with_name("no-rcheck").long("no-rcheck").conflicts_with("rcheck"))
+        .arg(clap::Arg::with_name("chrono").long("chrono").takes_value(true).help("Backtrack chronologically if a backjump would undo more than this number of levels"))
+        .arg(clap::Arg::with_name("vivify").long("vivify").help("Shorten learnt clauses by vivification"))
+        .arg(clap::Arg::with_name("no-vivify").long("no-vivify").conflicts_with("vivify"))
+        .arg(clap::Arg::with_name("vivify-irred").long("vivify-irred").help("Shorten problem clauses by vivification"))
+        .arg(clap::Arg::with_name("no-vivify-irred").long("no-vivify-irred").conflicts_with("vivify-irred"))
 
         .arg(clap::Arg::with_name("asymm").long("asymm").conflicts_with("core").help("Shrink clauses by asymmetric branching"))
         .arg(clap::Arg::with_name("no-asymm").long("no-asymm").conflicts_with("asymm").conflicts_with("core"))
         .arg(clap::Arg::with_name("elim").long("elim").conflicts_with("core").help("Perform variable elimination"))
         .arg(clap::Arg::with_name("no-elim").long("no-elim").conflicts_with("elim").conflicts_with("core"))
+        .arg(clap::Arg::with_name("gates").long("gates").conflicts_with("core").help("Use gate definitions to produce fewer resolvents during variable elimination"))
+        .arg(clap::Arg::with_name("no-gates").long("no-gates").conflicts_with("gates").conflicts_with("core"))
+        .arg(clap::Arg::with_name("equiv").long("equiv").conflicts_with("core").help("Substitute equivalent literals"))
+        .arg(clap::Arg::with_name("no-equiv").long("no-equiv").conflicts_with("equiv").conflicts_with("core"))
+        .arg(clap::Arg::with_name("bce").long("bce").conflicts_with("core").help("Perform blocked clause elimination"))
+        .arg(clap::Arg::with_name("no-bce").long("no-bce").conflicts_with("bce").conflicts_with("core"))
+        .arg(clap::Arg::with_name("cce").long("cce").conflicts_with("core").help("Eliminate covered clauses (implies --bce)"))
+        .arg(clap::Arg::with_name("abce").long("abce").conflicts_with("core").help("Eliminate asymmetric blocked clauses (implies --bce)"))
+        .arg(clap::Arg::with_name("bva").long("bva").conflicts_with("core").help("Perform bounded variable addition"))
+        .arg(clap::Arg::with_name("no-bva").long("no-bva").conflicts_with("bva").conflicts_with("core"))
         .arg(clap::Arg::with_name("grow").long("grow").takes_value(true).conflicts_with("core").help("Allow a variable elimination step to grow by a number of clauses"))
         .arg(clap::Arg::with_name("cl-lim").long("cl-lim").takes_value(true).conflicts_with("core").help("Variables are not eliminated if it produces a resolvent with a length above this limit. -1 means no limit"))
         .arg(clap::Arg::with_name("sub-lim").long("sub-lim").takes_value(true).conflicts_with("core").help("Do not check if subsumption against a clause larger than this. -1 means no limit."))
@@ -73,13 +118,47 @@ fn main() {
         builder.init();
     }
 
+    let mode =
+        if let Some(path) = matches.value_of("cube") {
+            let mut s = minisat::CubeSettings::default();
+
+            for &x in matches.value_of("cube-depth").and_then(|s| s.parse().ok()).iter() {
+                s.depth = x;
+            }
+
+            for &x in matches.value_of("cube-free").and_then(|s| s.parse().ok()).iter() {
+                if 0.0 <= x && x <= 1.0 {
+                    s.min_free = x;
+                }
+            }
+
+            minisat_rust::Mode::Cube(path::PathBuf::from(path), s)
+        } else if let Some(path) = matches.value_of("conquer") {
+            minisat_rust::Mode::Conquer(path::PathBuf::from(path))
+        } else if matches.is_present("icnf") {
+            minisat_rust::Mode::Incremental
+        } else if matches.is_present("backbone") {
+            minisat_rust::Mode::Backbone
+        } else if matches.is_present("mus") {
+            minisat_rust::Mode::Mus
+        } else {
+            minisat_rust::Mode::Solve
+        };
+
     let main = minisat_rust::MainOptions {
+        mode,
         strict: matches.is_present("strict"),
         pre: !matches.is_present("no-pre"),
         solve: !matches.is_present("no-solve"),
         in_path: path::PathBuf::from(matches.value_of("input").unwrap()),
         out_path: matches.value_of("output").map(|x| path::PathBuf::from(x)),
         dimacs_path: matches.value_of("dimacs").map(|x| path::PathBuf::from(x)),
+        stats_json: matches.value_of("stats-json").map(|x| path::PathBuf::from(x)),
+        checkpoint: matches.value_of("checkpoint").map(|x| {
+            let every = matches.value_of("checkpoint-every").and_then(|s| s.parse().ok()).filter(|&n| n > 0);
+            (path::PathBuf::from(x), every.unwrap_or(100000))
+        }),
+        resume: matches.value_of("resume").map(|x| path::PathBuf::from(x)),
     };
 
     let solver = {
@@ -156,6 +235,49 @@ fn main() {
                 }
             }
 
+            for &x in matches.value_of("branching").iter() {
+                match x {
+                    "vsids" => {
+                        s.heur.branching = Branching::Vsids;
+                    }
+                    "vmtf" => {
+                        s.heur.branching = Branching::Vmtf;
+                    }
+                    "lrb" => {
+                        s.heur.branching = Branching::Lrb;
+                    }
+                    "chb" => {
+                        s.heur.branching = Branching::Chb;
+                    }
+                    _ => {}
+                }
+            }
+
+            for &x in matches.value_of("stable-branching").iter() {
+                match x {
+                    "vsids" => {
+                        s.heur.stable_branching = Branching::Vsids;
+                    }
+                    "vmtf" => {
+                        s.heur.stable_branching = Branching::Vmtf;
+                    }
+                    "lrb" => {
+                        s.heur.stable_branching = Branching::Lrb;
+                    }
+                    "chb" => {
+                        s.heur.stable_branching = Branching::Chb;
+                    }
+                    _ => {}
+                }
+            }
+
+            if matches.is_present("target-phase") {
+                s.heur.target_phase = true;
+            }
+            if matches.is_present("no-target-phase") {
+                s.heur.target_phase = false;
+            }
+
             if matches.is_present("rnd-init") {
                 s.heur.rnd_init_act = true;
             }
@@ -186,6 +308,77 @@ fn main() {
                 }
             }
 
+            for &x in matches.value_of("restarts").iter() {
+                match x {
+                    "static" => {
+                        s.search.restart.policy = RestartPolicy::Static;
+                    }
+                    "glucose" => {
+                        s.search.restart.policy = RestartPolicy::Glucose;
+                    }
+                    "ema" => {
+                        s.search.restart.policy = RestartPolicy::Ema;
+                    }
+                    _ => {}
+                }
+            }
+
+            for &x in matches.value_of("lbd-margin").and_then(|s| s.parse().ok()).iter() {
+                if 0.0 < x {
+                    s.search.restart.lbd_margin = x;
+                }
+            }
+
+            for &x in matches.value_of("restart-block").and_then(|s| s.parse().ok()).iter() {
+                if 0.0 < x {
+                    s.search.restart.blocking = Some(x);
+                }
+            }
+            if matches.is_present("no-restart-block") {
+                s.search.restart.blocking = None;
+            }
+
+            if matches.is_present("modes") {
+                s.search.mode.switching = true;
+            }
+            if matches.is_present("no-modes") {
+                s.search.mode.switching = false;
+            }
+
+            if matches.is_present("rephase") {
+                s.search.rephase.enabled = true;
+            }
+            if matches.is_present("no-rephase") {
+                s.search.rephase.enabled = false;
+            }
+
+            if matches.is_present("local-search") {
+                s.search.local_search.standalone = true;
+            }
+
+            for &x in matches.value_of("ls-algorithm").iter() {
+                match x {
+                    "probsat" => {
+                        s.search.local_search.algorithm = LocalSearchAlgorithm::ProbSat;
+                    }
+                    "walksat" => {
+                        s.search.local_search.algorithm = LocalSearchAlgorithm::WalkSat;
+                    }
+                    _ => {}
+                }
+            }
+
+            for &x in matches.value_of("ls-flips").and_then(|s| s.parse().ok()).iter() {
+                s.search.local_search.max_flips = x;
+            }
+
+            if matches.is_present("walk") {
+                s.search.local_search.walk = true;
+            }
+            if matches.is_present("no-walk") {
+                s.search.local_search.walk = false;
+            }
+
             for &x in matches
                 .value_of("gc-frac")
                 .and_then(|s| s.parse().ok())
@@ -213,10 +406,39 @@ fn main() {
                 s.core.use_rcheck = false;
             }
 
+            for &x in matches
+                .value_of("chrono")
+                .and_then(|s| s.parse().ok())
+                .iter()
+            {
+                s.core.chrono = Some(x);
+            }
+
+            if matches.is_present("vivify") {
+                s.search.vivify.learnts = true;
+            }
+            if matches.is_present("no-vivify") {
+                s.search.vivify.learnts = false;
+            }
+
+            if matches.is_present("vivify-irred") {
+                s.search.vivify.clauses = true;
+            }
+            if matches.is_present("no-vivify-irred") {
+                s.search.vivify.clauses = false;
+            }
+
             s
         };
 
-        if matches.is_present("core") {
+        let threads = matches.value_of("threads").and_then(|s| s.parse().ok()).unwrap_or(1);
+        if threads > 1 && matches.is_present("core") {
+            let mut s = portfolio::PortfolioSettings::default();
+            s.threads = threads;
+            s.simp = false;
+            s.base.core = core_options;
+            minisat_rust::SolverOptions::Portfolio(s)
+        } else if matches.is_present("core") {
             minisat_rust::SolverOptions::Core(core_options)
         } else {
             let simp_options = {
@@ -237,6 +459,42 @@ fn main() {
                     s.simp.use_elim = false;
                 }
 
+                if matches.is_present("gates") {
+                    s.simp.use_gates = true;
+                }
+                if matches.is_present("no-gates") {
+                    s.simp.use_gates = false;
+                }
+
+                if matches.is_present("equiv") {
+                    s.simp.use_equiv = true;
+                }
+                if matches.is_present("no-equiv") {
+                    s.simp.use_equiv = false;
+                }
+
+                if matches.is_present("bce") {
+                    s.simp.use_bce = true;
+                }
+                if matches.is_present("no-bce") {
+                    s.simp.use_bce = false;
+                }
+                if matches.is_present("cce") {
+                    s.simp.use_bce = true;
+                    s.simp.use_cce = true;
+                }
+                if matches.is_present("abce") {
+                    s.simp.use_bce = true;
+                    s.simp.use_abce = true;
+                }
+
+                if matches.is_present("bva") {
+                    s.simp.use_bva = true;
+                }
+                if matches.is_present("no-bva") {
+                    s.simp.use_bva = false;
+                }
+
                 for &x in matches.value_of("grow").and_then(|s| s.parse().ok()).iter() {
                     s.simp.grow = x;
                 }
@@ -274,7 +532,14 @@ fn main() {
                 s
             };
 
-            minisat_rust::SolverOptions::Simp(simp_options)
+            if threads > 1 {
+                let mut s = portfolio::PortfolioSettings::default();
+                s.threads = threads;
+                s.base = simp_options;
+                minisat_rust::SolverOptions::Portfolio(s)
+            } else {
+                minisat_rust::SolverOptions::Simp(simp_options)
+            }
         }
     };
 
@@ -1,11 +1,15 @@
 use crate::sat::formula::{Lit, Var};
 
+pub mod backbone;
 pub mod dimacs;
 pub mod formula;
+pub mod ipasir;
 pub mod minisat;
+pub mod mus;
+pub mod portfolio;
 
 
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct Stats {
     pub solves: u64,
     pub restarts: u64,
@@ -14,7 +18,38 @@ pub struct Stats {
     pub conflicts: u64,
     pub propagations: u64,
     pub tot_literals: u64,
-    pub del_literals: u64,
+    pub del_literals: u64, // Literals removed from learnt clauses by minimization.
+    pub learnts: u64, // Learnt clauses, units included.
+    pub learnt_lbds: u64, // Sum of the LBDs of the learnt clauses.
+    pub conflict_trails: u64, // Sum of the trail sizes at conflicts.
+    pub reductions: u64,
+    pub garbage_collections: u64,
+    pub eliminated_vars: u64,
+    pub asymm_lits: u64,
+    pub equiv_vars: u64, // Variables substituted by equivalent literals.
+    pub blocked_clauses: u64, // Removed as blocked (covered, asymmetric blocked) clauses.
+    pub added_vars: u64, // Variables added by bounded variable addition.
+    pub vivified_clauses: u64, // Clauses shortened by vivification.
+    pub vivified_literals: u64, // Literals removed by vivification.
+    pub gates: u64, // Eliminated variables defined by gates.
+    pub chrono_backtracks: u64, // Conflicts followed by a chronological backtrack.
+    pub blocked_restarts: u64, // Restarts postponed by the trail-based blocking.
+    pub mode_switches: u64, // Switches between the focused and the stable mode.
+    pub rephases: u64, // Resets of the saved phases.
+    pub walk_flips: u64, // Flips of the local search.
+    pub parse_time: f64, // Seconds, filled in by the caller of the solver.
+    pub simplify_time: f64,
+    pub search_time: f64,
+}
+
+impl Stats {
+    pub fn avg_lbd(&self) -> f64 {
+        if self.learnts == 0 { 0.0 } else { self.learnt_lbds as f64 / self.learnts as f64 }
+    }
+
+    pub fn avg_trail(&self) -> f64 {
+        if self.conflicts == 0 { 0.0 } else { self.conflict_trails as f64 / self.conflicts as f64 }
+    }
 }
 
 
@@ -25,6 +60,14 @@ pub enum SolveRes<Solver> {
 }
 
 
+// Result of a solve that keeps the solver for further solves.
+pub enum IncrementalRes {
+    UnSat(Vec<Lit>), // The failed assumptions (empty if the formula itself is unsatisfiable).
+    Sat(Vec<Lit>),
+    Interrupted(f64),
+}
+
+
 pub trait Solver: Sized {
     fn n_vars(&self) -> usize;
     fn n_clauses(&self) -> usize;
@@ -34,3 +77,12 @@ pub trait Solver: Sized {
     fn solve_limited(self, _: &minisat::budget::Budget, _: &[Lit]) -> SolveRes<Self>;
     fn stats(&self) -> Stats;
 }
+
+
+// Solvers that keep learnt clauses between solves under different assumptions.
+pub trait IncrementalSolver: Solver {
+    // Keeps 'v' from being eliminated by the preprocessing, so that it may occur in later clauses and
+    // assumptions.
+    fn freeze(&mut self, _: Var) {}
+    fn solve_incremental(&mut self, _: &minisat::budget::Budget, _: &[Lit]) -> IncrementalRes;
+}
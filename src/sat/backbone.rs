@@ -0,0 +1,85 @@
+use crate::sat::{IncrementalRes, IncrementalSolver};
+use crate::sat::formula::{Idx, Lit, Var};
+use crate::sat::minisat::budget::Budget;
+
+
+// Number of candidates refuted by one solve.
+const CHUNK: usize = 16;
+
+
+pub enum BackboneRes {
+    Backbone(Vec<Lit>), // Literals true in every model, ordered by variable.
+    UnSat,
+    Interrupted,
+}
+
+
+// Description:
+//   Computes the backbone of the formula over 'vars', i.e. the literals true in every model. The
+//   candidates are the literals of a first model, refuted in chunks by assuming their negations:
+//   a model drops every candidate it falsifies, a single failed assumption is a backbone literal
+//   and the first literal of a larger core is tested alone next. Backbone literals are added to the
+//   solver as unit clauses, so the remaining solves build on them. The variables are frozen, they
+//   must not be eliminated already.
+//
+// Output:
+//   The backbone, unless the formula is unsatisfiable or the budget runs out.
+pub fn backbone<S: IncrementalSolver>(solver: &mut S, budget: &Budget, vars: &[Var]) -> BackboneRes {
+    for &v in vars.iter() {
+        solver.freeze(v);
+    }
+
+    let mut value = vec![false; solver.n_vars()];
+    let mut candidates: Vec<Lit> =
+        match solver.solve_incremental(budget, &[]) {
+            IncrementalRes::Sat(model) => {
+                for lit in model {
+                    value[lit.var().idx()] = !lit.sign();
+                }
+                vars.iter().map(|&v| v.sign_lit(!value[v.idx()])).collect()
+            }
+
+            IncrementalRes::UnSat(_) => return BackboneRes::UnSat,
+            IncrementalRes::Interrupted(_) => return BackboneRes::Interrupted,
+        };
+
+    let mut backbone = Vec::new();
+    let mut chunk = CHUNK;
+    while !candidates.is_empty() {
+        let assumptions: Vec<Lit> = candidates.iter().take(chunk).map(|&lit| !lit).collect();
+        match solver.solve_incremental(budget, &assumptions) {
+            IncrementalRes::Sat(model) => {
+                for lit in model {
+                    value[lit.var().idx()] = !lit.sign();
+                }
+                candidates.retain(|lit| value[lit.var().idx()] != lit.sign());
+                chunk = CHUNK;
+            }
+
+            IncrementalRes::UnSat(failed) => {
+                match failed[..] {
+                    [] => return BackboneRes::UnSat,
+
+                    [assumption] => {
+                        let lit = !assumption;
+                        candidates.retain(|&c| c != lit);
+                        solver.add_clause(&[lit]);
+                        backbone.push(lit);
+                        chunk = CHUNK;
+                    }
+
+                    _ => {
+                        let i = candidates.iter().position(|&c| c == !failed[0]).unwrap();
+                        candidates.swap(0, i);
+                        chunk = 1;
+                    }
+                }
+            }
+
+            IncrementalRes::Interrupted(_) => return BackboneRes::Interrupted,
+        }
+    }
+
+    backbone.sort_by_key(|lit| lit.var());
+    BackboneRes::Backbone(backbone)
+}
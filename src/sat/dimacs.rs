@@ -4,7 +4,7 @@ use std::io::{Seek, SeekFrom};
 use std::collections::{HashMap, HashSet};
 use flate2::read::GzDecoder;
 use crate::sat::formula::{Lit, Var, VarMap};
-use crate::sat::{SolveRes, Solver};
+use crate::sat::{IncrementalRes, SolveRes, Solver};
 
 
 pub fn write<W: io::Write, S: Solver>(_: W, _: &S) -> io::Result<()> {
@@ -43,6 +43,91 @@ pub fn parse<R: io::Read, S: Solver>(
 }
 
 
+// Reads an iCNF file: clauses are added to the solver as they come, and every assumption line
+// ('a ... 0') is a query, passed to 'query' with the assumptions and the current 'backward_subst'.
+pub fn parse_icnf_file<P, S, F>(path: P, solver: &mut S, query: F) -> io::Result<VarMap<i32>>
+where
+    P: AsRef<path::Path>,
+    S: Solver,
+    F: FnMut(&mut S, &[Lit], &VarMap<i32>) -> io::Result<()>,
+{
+    let mut reader = io::BufReader::new(fs::File::open(path)?);
+    {
+        let gz = GzDecoder::new(&mut reader);
+        if gz.header().is_some() {
+            return parse_icnf(gz, solver, query);
+        }
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+    parse_icnf(reader, solver, query)
+}
+
+pub fn parse_icnf<R, S, F>(reader: R, solver: &mut S, mut query: F) -> io::Result<VarMap<i32>>
+where
+    R: io::Read,
+    S: Solver,
+    F: FnMut(&mut S, &[Lit], &VarMap<i32>) -> io::Result<()>,
+{
+    let mut subst = Subst::new(solver);
+    DimacsParser::parse_icnf(reader, |line| {
+        match line {
+            IcnfLine::Clause(raw) => {
+                subst.add_clause(raw);
+                Ok(())
+            }
+
+            IcnfLine::Assumptions(raw) => {
+                let assumptions = subst.lits(raw);
+                query(subst.solver, &assumptions, &subst.backward_subst)
+            }
+        }
+    })?;
+    Ok(subst.backward_subst)
+}
+
+
+// Clauses of a group CNF, over variables created in the solver (no clauses are added to it).
+pub struct Gcnf {
+    pub hard: Vec<Vec<Lit>>, // Group 0.
+    pub groups: Vec<Vec<Vec<Lit>>>, // Group 'g' at index 'g - 1'.
+    pub backward_subst: VarMap<i32>,
+}
+
+pub fn parse_gcnf_file<P: AsRef<path::Path>, S: Solver>(path: P, solver: &mut S, validate: bool) -> io::Result<Gcnf> {
+    let mut reader = io::BufReader::new(fs::File::open(path)?);
+    {
+        let gz = GzDecoder::new(&mut reader);
+        if gz.header().is_some() {
+            return parse_gcnf(gz, solver, validate);
+        }
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+    parse_gcnf(reader, solver, validate)
+}
+
+// Reads a group CNF ('p gcnf <vars> <clauses> <groups>', every clause prefixed by '{<group>}'). A
+// plain CNF is read as a group CNF with every clause in a group of its own.
+pub fn parse_gcnf<R: io::Read, S: Solver>(reader: R, solver: &mut S, validate: bool) -> io::Result<Gcnf> {
+    let mut subst = Subst::new(solver);
+    let mut hard = Vec::new();
+    let mut groups: Vec<Vec<Vec<Lit>>> = Vec::new();
+    DimacsParser::parse_gcnf(reader, validate, |group, raw| {
+        let clause = subst.lits(raw);
+        if group == 0 {
+            hard.push(clause);
+        } else {
+            if groups.len() < group {
+                groups.resize(group, Vec::new());
+            }
+            groups[group - 1].push(clause);
+        }
+    })?;
+    Ok(Gcnf { hard, groups, backward_subst: subst.backward_subst })
+}
+
+
 pub fn write_result<W: io::Write, S>(
     mut writer: W,
     result: SolveRes<S>,
@@ -59,9 +144,11 @@ pub fn write_result<W: io::Write, S>(
 
         SolveRes::SAT(model, _) => {
             writeln!(writer, "SAT")?;
+            // Variables introduced by the solver itself are not in 'backward_subst':
             for lit in model.iter() {
-                let var_id = backward_subst[&lit.var()];
-                write!(writer, "{} ", if lit.sign() { -var_id } else { var_id })?;
+                if let Some(&var_id) = backward_subst.get(&lit.var()) {
+                    write!(writer, "{} ", if lit.sign() { -var_id } else { var_id })?;
+                }
             }
             writeln!(writer, "0")?;
         }
@@ -70,6 +157,130 @@ pub fn write_result<W: io::Write, S>(
 }
 
 
+// Writes the result of one iCNF query: the model for SAT, the failed assumptions for UNSAT.
+pub fn write_query_result<W: io::Write>(
+    mut writer: W,
+    result: &IncrementalRes,
+    backward_subst: &VarMap<i32>,
+) -> io::Result<()> {
+    match result {
+        IncrementalRes::UnSat(failed) => {
+            writeln!(writer, "UNSAT")?;
+            write_ids(&mut writer, "", lit_ids(failed, backward_subst))
+        }
+
+        IncrementalRes::Interrupted(_) => {
+            writeln!(writer, "INDET")
+        }
+
+        IncrementalRes::Sat(model) => {
+            writeln!(writer, "SAT")?;
+            write_ids(&mut writer, "", lit_ids(model, backward_subst))
+        }
+    }
+}
+
+
+// Writes the formula from 'reader' in the iCNF format, followed by a query under every cube.
+pub fn write_icnf_file<P: AsRef<path::Path>, W: io::Write>(
+    path: P,
+    writer: W,
+    cubes: &[Vec<Lit>],
+    backward_subst: &VarMap<i32>,
+) -> io::Result<()> {
+    let mut reader = io::BufReader::new(fs::File::open(path)?);
+    {
+        let gz = GzDecoder::new(&mut reader);
+        if gz.header().is_some() {
+            return write_icnf(gz, writer, cubes, backward_subst);
+        }
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+    write_icnf(reader, writer, cubes, backward_subst)
+}
+
+pub fn write_icnf<R: io::Read, W: io::Write>(
+    reader: R,
+    mut writer: W,
+    cubes: &[Vec<Lit>],
+    backward_subst: &VarMap<i32>,
+) -> io::Result<()> {
+    writeln!(writer, "p inccnf")?;
+
+    let mut res = Ok(());
+    DimacsParser::parse(reader, false, |cl| {
+        if res.is_ok() {
+            res = write_ids(&mut writer, "", cl.into_iter());
+        }
+    })?;
+    res?;
+
+    for cube in cubes.iter() {
+        write_ids(&mut writer, "a ", lit_ids(cube, backward_subst))?;
+    }
+    Ok(())
+}
+
+// Writes the literals in DIMACS, after 'prefix' and terminated by 0.
+pub fn write_lits<W: io::Write>(mut writer: W, prefix: &str, lits: &[Lit], backward_subst: &VarMap<i32>) -> io::Result<()> {
+    write_ids(&mut writer, prefix, lit_ids(lits, backward_subst))
+}
+
+// Variables introduced by the solver itself are not in 'backward_subst' and are left out.
+fn lit_ids<'a>(lits: &'a [Lit], backward_subst: &'a VarMap<i32>) -> impl Iterator<Item = i32> + 'a {
+    lits.iter().filter_map(move |lit| {
+        backward_subst.get(&lit.var()).map(|&var_id| if lit.sign() { -var_id } else { var_id })
+    })
+}
+
+fn write_ids<W: io::Write, I: Iterator<Item = i32>>(writer: &mut W, prefix: &str, ids: I) -> io::Result<()> {
+    write!(writer, "{}", prefix)?;
+    for id in ids {
+        write!(writer, "{} ", id)?;
+    }
+    writeln!(writer, "0")
+}
+
+
+// Reads the assumption lines ('a ... 0') of an iCNF file, clauses are ignored.
+pub fn parse_cubes_file<P: AsRef<path::Path>>(path: P, backward_subst: &VarMap<i32>) -> io::Result<Vec<Vec<Lit>>> {
+    let mut reader = io::BufReader::new(fs::File::open(path)?);
+    {
+        let gz = GzDecoder::new(&mut reader);
+        if gz.header().is_some() {
+            return parse_cubes(gz, backward_subst);
+        }
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+    parse_cubes(reader, backward_subst)
+}
+
+pub fn parse_cubes<R: io::Read>(reader: R, backward_subst: &VarMap<i32>) -> io::Result<Vec<Vec<Lit>>> {
+    let forward_subst: HashMap<i32, Var> = backward_subst.iter().map(|(v, &var_id)| (var_id, v)).collect();
+
+    let mut cubes = Vec::new();
+    DimacsParser::parse_icnf(reader, |line| {
+        if let IcnfLine::Assumptions(raw) = line {
+            let mut cube = Vec::with_capacity(raw.len());
+            for lit_id in raw {
+                match forward_subst.get(&lit_id.abs()) {
+                    Some(v) => cube.push(v.sign_lit(lit_id < 0)),
+                    None => {
+                        return Err(io::Error::other(format!("PARSE ERROR! Unknown variable {} in a cube", lit_id.abs())));
+                    }
+                }
+            }
+            cubes.push(cube);
+        }
+        Ok(())
+    })?;
+
+    Ok(cubes)
+}
+
+
 pub fn validate_model_file<P: AsRef<path::Path>>(
     path: P,
     backward_subst: &VarMap<i32>,
@@ -95,7 +306,10 @@ pub fn validate_model<R: io::Read>(
     let mut lits = HashSet::new();
     for lit in model.iter() {
         let lit_id = {
-            let var_id = backward_subst[&lit.var()];
+            let var_id = match backward_subst.get(&lit.var()) {
+                Some(&var_id) => var_id,
+                None => continue,
+            };
             if lit.sign() {
                 -var_id
             } else {
@@ -144,10 +358,14 @@ impl<'s, S: Solver> Subst<'s, S> {
     }
 
     pub fn add_clause(&mut self, raw: Vec<i32>) {
-        let lits: Vec<Lit> = raw.iter().map(|&lit_id| self.lit_by_id(lit_id)).collect();
+        let lits = self.lits(raw);
         self.solver.add_clause(&lits[..]);
     }
 
+    pub fn lits(&mut self, raw: Vec<i32>) -> Vec<Lit> {
+        raw.iter().map(|&lit_id| self.lit_by_id(lit_id)).collect()
+    }
+
     fn lit_by_id(&mut self, lit_id: i32) -> Lit {
         if !self.forward_subst.contains_key(&lit_id.abs()) {
             // TODO: finish it
@@ -168,6 +386,12 @@ impl<'s, S: Solver> Subst<'s, S> {
 }
 
 
+enum IcnfLine {
+    Clause(Vec<i32>),
+    Assumptions(Vec<i32>),
+}
+
+
 struct DimacsParser<'p> {
     reader: str::Chars<'p>,
     cur: Option<char>,
@@ -177,6 +401,70 @@ struct DimacsParser<'p> {
 
 impl<'p> DimacsParser<'p> {
     pub fn parse<R: io::Read + 'p, F: FnMut(Vec<i32>) -> ()>(
+        mut reader: R,
+        validate: bool,
+        mut clause: F,
+    ) -> io::Result<()> {
+        let mut buf = String::new();
+        reader.read_to_string(&mut buf)?;
+
+        let mut p = DimacsParser {
+            reader: buf.chars(),
+            cur: None,
+            vars: HashSet::new(),
+            clauses: 0,
+        };
+        p.next()?;
+        p.parse_me(validate, false, |_, c| clause(c))
+    }
+
+    // Clauses and assumption lines are passed in the order of the file, the 'p inccnf' header is
+    // optional.
+    pub fn parse_icnf<R: io::Read + 'p, F: FnMut(IcnfLine) -> io::Result<()>>(
+        mut reader: R,
+        mut line: F,
+    ) -> io::Result<()> {
+        let mut buf = String::new();
+        reader.read_to_string(&mut buf)?;
+
+        let mut p = DimacsParser {
+            reader: buf.chars(),
+            cur: None,
+            vars: HashSet::new(),
+            clauses: 0,
+        };
+        p.next()?;
+
+        loop {
+            p.skip_whitespace()?;
+            match p.current() {
+                None => return Ok(()),
+
+                Some('c') => {
+                    p.skip_line()?;
+                }
+
+                Some('p') => {
+                    p.consume("p inccnf")?;
+                }
+
+                Some('a') => {
+                    p.next()?;
+                    let a = p.parse_clause()?;
+                    line(IcnfLine::Assumptions(a))?;
+                }
+
+                _ => {
+                    let c = p.parse_clause()?;
+                    line(IcnfLine::Clause(c))?;
+                }
+            }
+        }
+    }
+
+    // Clauses are passed with their group, 0 for the hard clauses. Without groups ('p cnf'), the
+    // clauses are numbered from 1.
+    pub fn parse_gcnf<R: io::Read + 'p, F: FnMut(usize, Vec<i32>)>(
         mut reader: R,
         validate: bool,
         clause: F,
@@ -191,17 +479,20 @@ impl<'p> DimacsParser<'p> {
             clauses: 0,
         };
         p.next()?;
-        p.parse_me(validate, clause)
+        p.parse_me(validate, true, clause)
     }
 
-    fn parse_me<F: FnMut(Vec<i32>) -> ()>(
+    // A 'p gcnf <vars> <clauses> <groups>' header is accepted if 'grouped', then every clause is
+    // prefixed by '{<group>}'. Otherwise, the group of a clause is its number.
+    fn parse_me<F: FnMut(usize, Vec<i32>)>(
         &mut self,
         validate: bool,
+        grouped: bool,
         mut clause: F,
     ) -> io::Result<()> {
         enum State {
             Waiting,
-            Parsing(usize, usize),
+            Parsing(usize, usize, Option<usize>),
         }
 
         let mut state = State::Waiting;
@@ -214,14 +505,23 @@ impl<'p> DimacsParser<'p> {
                     }
 
                     _ => {
-                        self.consume("p cnf")?;
+                        let groups = grouped && {
+                            self.consume("p ")?;
+                            self.current() == Some('g')
+                        };
+                        self.consume(match (grouped, groups) {
+                            (false, _) => "p cnf",
+                            (true, false) => "cnf",
+                            (true, true) => "gcnf",
+                        })?;
                         let vars = self.next_uint()?;
                         let clauses = self.next_uint()?;
-                        state = State::Parsing(vars, clauses);
+                        let groups = if groups { Some(self.next_uint()?) } else { None };
+                        state = State::Parsing(vars, clauses, groups);
                     }
                 },
 
-                State::Parsing(vars, clauses) => match self.current() {
+                State::Parsing(vars, clauses, groups) => match self.current() {
                     Some('c') => {
                         self.skip_line()?;
                     }
@@ -242,8 +542,21 @@ impl<'p> DimacsParser<'p> {
                     }
 
                     _ => {
+                        let group = match groups {
+                            Some(groups) => {
+                                self.consume("{")?;
+                                let group = self.next_uint()?;
+                                self.consume("}")?;
+                                if validate && group > groups {
+                                    return Err(io::Error::other(format!("PARSE ERROR! Group {} of {} declared", group, groups)));
+                                }
+                                group
+                            }
+
+                            None => self.clauses + 1,
+                        };
                         let c = self.parse_clause()?;
-                        clause(c);
+                        clause(group, c);
                     }
                 },
             }
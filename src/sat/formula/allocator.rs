@@ -102,6 +102,21 @@ impl RegionAllocator {
     }
 }
 
+impl Clone for RegionAllocator {
+    fn clone(&self) -> Self {
+        let mut ra = RegionAllocator::with_capacity(self.capacity, self.align);
+        if self.offset > 0 {
+            unsafe { ptr::copy_nonoverlapping(self.memory, ra.memory, self.offset); }
+        }
+        ra.offset = self.offset;
+        ra
+    }
+}
+
+// The region is owned by the allocator alone, so it may move to another thread. It is not 'Sync',
+// since 'get_mut' hands out mutable references through a shared one.
+unsafe impl Send for RegionAllocator {}
+
 impl Drop for RegionAllocator {
     fn drop(&mut self) {
         if !self.memory.is_null() {
@@ -17,14 +17,21 @@ impl DecisionLevel {
     pub fn offset_from_ground(&self) -> usize {
         (self.0 - 1) as usize
     }
+
+    pub fn previous(&self) -> DecisionLevel {
+        assert!(!self.is_ground());
+        DecisionLevel(self.0 - 1)
+    }
 }
 
 
+#[derive(Clone)]
 pub struct VarData {
     pub reason: Option<ClauseRef>,
     pub level: DecisionLevel,
 }
 
+#[derive(Clone)]
 pub struct Assignment {
     assign: Vec<LBool>,
     vd: Vec<VarData>,
@@ -102,28 +109,51 @@ impl Assignment {
 
     #[inline]
     pub fn assign_lit(&mut self, lit: Lit, reason: Option<ClauseRef>) {
+        let level = self.current_level();
+        self.assign_lit_at(lit, reason, level);
+    }
+
+    // Assigns a literal implied at a level below the current one (chronological backtracking). The
+    // trail is not ordered by levels anymore after that.
+    #[inline]
+    pub fn assign_lit_at(&mut self, lit: Lit, reason: Option<ClauseRef>, level: DecisionLevel) {
         unsafe {
             let val = self.assign.get_unchecked_mut(lit.var_index());
             assert!(val.is_undef());
             *val = lit.pos_assignment();
-            *self.vd.get_unchecked_mut(lit.var_index()) =
-                VarData { reason: reason, level: DecisionLevel(self.lim.len()) };
+            *self.vd.get_unchecked_mut(lit.var_index()) = VarData { reason: reason, level };
             self.trail.push(lit);
         }
     }
 
+    // Sets the reason of the true literal 'lit' explained after it was assigned.
+    pub fn set_reason(&mut self, lit: Lit, reason: ClauseRef) {
+        assert!(self.is_assigned_pos(lit));
+        self.vd[lit.var_index()].reason = Some(reason);
+    }
+
     // Revert to the state at given level (keeping all assignment at 'level' but not beyond).
+    // Assignments made at lower levels out of order stay on the trail and are propagated again.
     pub fn backtrack_to(&mut self, target_level: DecisionLevel) {
         if let Some(&target) = self.lim.get(target_level.0) {
-            for &lit in &self.trail[target..] {
+            let mut kept = target;
+            for i in target..self.trail.len() {
+                let lit = self.trail[i];
                 unsafe {
-                    *self.assign.get_unchecked_mut(lit.var_index()) = LBool::Undef;
-                    self.vd.get_unchecked_mut(lit.var_index()).reason = None;
+                    let vd = self.vd.get_unchecked_mut(lit.var_index());
+                    if vd.level <= target_level {
+                        self.trail[kept] = lit;
+                        kept += 1;
+                    } else {
+                        *self.assign.get_unchecked_mut(lit.var_index()) = LBool::Undef;
+                        vd.reason = None;
+                    }
                 }
             }
 
-            self.trail.truncate(target);
+            self.trail.truncate(kept);
             self.lim.truncate(target_level.0);
+            self.qhead = cmp::min(self.qhead, target);
         }
 
         self.qhead = cmp::min(self.qhead, self.trail.len());
@@ -214,6 +244,14 @@ impl Assignment {
     }
 
 
+    #[inline]
+    pub fn level(&self, var: Var) -> DecisionLevel {
+        unsafe {
+            assert!(!self.assign.get_unchecked(var.index()).is_undef());
+            self.vd.get_unchecked(var.index()).level
+        }
+    }
+
     #[inline]
     pub fn vardata(&self, lit: Lit) -> &VarData {
         unsafe {
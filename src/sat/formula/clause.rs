@@ -6,6 +6,7 @@ pub use super::clause_header::*;
 const FLAG_BITS : usize = 4;
 const FLAG_DELETED : u32 = 0x01;
 const FLAG_TOUCHED : u32 = 0x02;
+const FLAG_VIVIFIED : u32 = 0x04;
 const FLAG_RELOCED : u32 = 0x08;
 
 pub const MIN_CLAUSE_SIZE : usize = 2;
@@ -67,6 +68,14 @@ impl Clause {
             self.mark &= !FLAG_TOUCHED;
         }
     }
+
+    pub fn is_vivified(&self) -> bool {
+        (self.mark & FLAG_VIVIFIED) != 0
+    }
+
+    pub fn set_vivified(&mut self) {
+        self.mark |= FLAG_VIVIFIED;
+    }
 }
 
 impl fmt::Debug for Clause {
@@ -93,6 +102,7 @@ impl fmt::Debug for Clause {
 pub struct ClauseRef(allocator::Ref);
 
 
+#[derive(Clone)]
 pub struct ClauseAllocator {
     ra: allocator::RegionAllocator,
     lc: LegacyCounter,
@@ -166,6 +176,10 @@ impl ClauseAllocator {
         (self.lc.wasted as f64) > (self.lc.size as f64) * gf
     }
 
+    pub fn allocated_bytes(&self) -> usize {
+        self.ra.allocated_bytes()
+    }
+
 
     #[inline]
     pub fn view(&self, cref: ClauseRef) -> &Clause {
@@ -223,6 +237,7 @@ impl<'a> ClauseGC<'a> {
 }
 
 
+#[derive(Clone)]
 struct LegacyCounter {
     size: usize,
     wasted: usize,
@@ -1,18 +1,47 @@
 use std::num;
 
 
+// The LBD is kept small enough to share the word of the tag, so learnt clauses don't make the headers
+// of all clauses larger.
 #[derive(Clone, Copy)]
 pub enum ClauseHeader {
     Clause { abstraction: Option<num::NonZeroU32> },
-    Learnt { activity: f32 }
+    Learnt { lbd: u16, activity: f32 }
 }
 
 impl ClauseHeader {
+    pub fn learnt(activity: f32, lbd: u32) -> ClauseHeader {
+        ClauseHeader::Learnt { lbd: lbd.min(u16::MAX as u32) as u16, activity }
+    }
+
     pub fn activity(&self) -> f32 {
-        if let ClauseHeader::Learnt { activity } = self {
+        if let ClauseHeader::Learnt { activity, .. } = self {
             *activity
         } else {
             panic!("Learnt expected");
         }
     }
+
+    // Number of distinct decision levels in the clause when it was learnt (literal block distance),
+    // saturated at 'u16::MAX'.
+    pub fn lbd(&self) -> u32 {
+        if let ClauseHeader::Learnt { lbd, .. } = self {
+            *lbd as u32
+        } else {
+            panic!("Learnt expected");
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+    use super::*;
+
+    #[test]
+    fn test_header_size() {
+        assert_eq!(mem::size_of::<ClauseHeader>(), 8);
+        assert_eq!(ClauseHeader::learnt(1.0, 100_000).lbd(), u16::MAX as u32);
+    }
 }
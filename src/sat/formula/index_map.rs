@@ -40,6 +40,7 @@ impl Idx for Lit {
 }
 
 
+#[derive(Clone)]
 pub struct IdxMap<K: Idx, V> {
     map: vec_map::VecMap<V>,
     ph: marker::PhantomData<K>,
@@ -132,6 +133,7 @@ impl<'a, K: Idx, V: 'a> Iterator for IterMut<'a, K, V> {
 }
 
 
+#[derive(Clone)]
 pub struct IdxVec<K: Idx, V> {
     vec: Vec<V>,
     ph: marker::PhantomData<K>,
@@ -180,6 +182,7 @@ impl<K: Idx, V> ops::IndexMut<K> for IdxVec<K, V> {
 }
 
 
+#[derive(Clone)]
 pub struct IdxHeap<K: Idx> {
     heap: Vec<K>,
     index: vec_map::VecMap<usize>,
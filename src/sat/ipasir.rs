@@ -0,0 +1,299 @@
+use std::mem;
+use std::collections::HashSet;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::{Arc, Mutex};
+use crate::sat::{IncrementalRes, IncrementalSolver};
+use crate::sat::formula::{Idx, Lit, Var};
+use crate::sat::minisat::{self, CallbackExchange, ClauseExchange, ExportCallback};
+use crate::sat::minisat::budget::Budget;
+
+
+// Solvers usable behind the IPASIR interface.
+pub trait Backend: IncrementalSolver {
+    fn set_exchange(&mut self, exchange: Box<dyn ClauseExchange>);
+}
+
+impl Backend for minisat::CoreSolver {
+    fn set_exchange(&mut self, exchange: Box<dyn ClauseExchange>) {
+        minisat::CoreSolver::set_exchange(self, exchange);
+    }
+}
+
+impl Backend for minisat::SimpSolver {
+    fn set_exchange(&mut self, exchange: Box<dyn ClauseExchange>) {
+        minisat::SimpSolver::set_exchange(self, exchange);
+    }
+}
+
+
+enum State {
+    Input,
+    Model(Vec<bool>), // Satisfiable, values of the variables (by index).
+    Failed(HashSet<Lit>), // Unsatisfiable, failed assumptions.
+}
+
+
+// Called with the DIMACS literals of a learnt clause.
+pub type LearnCallback = Box<dyn FnMut(&[i32]) + Send>;
+
+
+// The IPASIR state machine over an incremental solver. Literals are DIMACS integers, variables are
+// created on their first use. Assumptions are valid for the next solve only. 'i32::MIN' has no
+// variable: it is ignored as an assumption, like 0, and rejected in clauses.
+pub struct Ipasir<S> {
+    solver: S,
+    vars: Vec<Var>, // Solver variable of every DIMACS variable (by 'id - 1').
+    ids: Arc<Mutex<Vec<i32>>>, // DIMACS variable of every solver variable (by index), 0 if none.
+    clause: Vec<Lit>,
+    assumptions: Vec<Lit>,
+    budget: Budget,
+    state: State,
+}
+
+impl<S: Backend> Ipasir<S> {
+    pub fn new(solver: S) -> Self {
+        let mut budget = Budget::new();
+        budget.off();
+        Ipasir {
+            solver,
+            vars: Vec::new(),
+            ids: Arc::new(Mutex::new(Vec::new())),
+            clause: Vec::new(),
+            assumptions: Vec::new(),
+            budget,
+            state: State::Input,
+        }
+    }
+
+    // Adds a literal to the current clause, 0 finishes the clause. Panics on 'i32::MIN' (aborts
+    // through the C interface), which would otherwise be dropped from the clause.
+    pub fn add(&mut self, lit_or_zero: i32) {
+        if lit_or_zero == 0 {
+            let clause = mem::take(&mut self.clause);
+            self.solver.add_clause(&clause);
+        } else {
+            let lit = self.lit(lit_or_zero).expect("Invalid literal");
+            self.clause.push(lit);
+        }
+        self.state = State::Input;
+    }
+
+    pub fn assume(&mut self, lit: i32) {
+        if let Some(lit) = self.lit(lit) {
+            self.assumptions.push(lit);
+        }
+        self.state = State::Input;
+    }
+
+    // Returns 10 if satisfiable, 20 if unsatisfiable and 0 if interrupted.
+    pub fn solve(&mut self) -> i32 {
+        let assumptions = mem::take(&mut self.assumptions);
+        let (code, state) =
+            match self.solver.solve_incremental(&self.budget, &assumptions) {
+                IncrementalRes::Sat(model) => {
+                    let mut values = vec![false; self.solver.n_vars()];
+                    for lit in model {
+                        values[lit.var().idx()] = !lit.sign();
+                    }
+                    (10, State::Model(values))
+                }
+
+                IncrementalRes::UnSat(failed) => (20, State::Failed(failed.into_iter().collect())),
+                IncrementalRes::Interrupted(_) => (0, State::Input),
+            };
+
+        self.state = state;
+        code
+    }
+
+    // Returns 'lit' if it is true in the model, '-lit' if it is false, 0 if the variable is unknown
+    // or there is no model.
+    pub fn val(&self, lit: i32) -> i32 {
+        match self.state {
+            State::Model(ref values) => {
+                match var_index(lit).and_then(|i| self.vars.get(i)) {
+                    Some(v) => if values[v.idx()] == (lit > 0) { lit } else { -lit },
+                    None => 0,
+                }
+            }
+
+            _ => 0,
+        }
+    }
+
+    // Whether the assumption 'lit' was used to prove the last unsatisfiability.
+    pub fn failed(&self, lit: i32) -> bool {
+        match self.state {
+            State::Failed(ref failed) => {
+                match var_index(lit).and_then(|i| self.vars.get(i)) {
+                    Some(v) => failed.contains(&v.sign_lit(lit < 0)),
+                    None => false,
+                }
+            }
+
+            _ => false,
+        }
+    }
+
+    // The solver is interrupted as soon as 'terminate' returns TRUE.
+    pub fn set_terminate(&mut self, terminate: Option<Box<dyn Fn() -> bool + Send + Sync>>) {
+        self.budget.set_terminate(terminate);
+    }
+
+    // Passes every learnt clause of at most 'max_len' literals to 'learn'.
+    pub fn set_learn(&mut self, max_len: usize, learn: Option<LearnCallback>) {
+        let ids = self.ids.clone();
+        let export = learn.map(|mut learn| {
+            let mut buf = Vec::new();
+            Box::new(move |clause: &[Lit], _: u32| {
+                buf.clear();
+                let ids = ids.lock().unwrap();
+                for lit in clause.iter() {
+                    // Clauses over variables introduced by the solver itself are not passed:
+                    match ids.get(lit.var().idx()) {
+                        Some(&id) if id != 0 => buf.push(if lit.sign() { -id } else { id }),
+                        _ => return,
+                    }
+                }
+                learn(&buf);
+            }) as ExportCallback
+        });
+        self.solver.set_exchange(Box::new(CallbackExchange::new(max_len, u32::MAX, export, None)));
+    }
+
+    pub fn solver(&self) -> &S {
+        &self.solver
+    }
+
+    fn lit(&mut self, lit: i32) -> Option<Lit> {
+        let index = var_index(lit).filter(|&i| i < i32::MAX as usize)?;
+        while self.vars.len() <= index {
+            // Any variable may occur in later clauses and assumptions:
+            let v = self.solver.new_var(None, true);
+            self.solver.freeze(v);
+            self.vars.push(v);
+
+            let mut ids = self.ids.lock().unwrap();
+            if ids.len() <= v.idx() {
+                ids.resize(v.idx() + 1, 0);
+            }
+            ids[v.idx()] = self.vars.len() as i32;
+        }
+
+        Some(self.vars[index].sign_lit(lit < 0))
+    }
+}
+
+
+// Index of the DIMACS variable of 'lit' ('i32::MIN' included), None for 0.
+fn var_index(lit: i32) -> Option<usize> {
+    (lit.unsigned_abs() as usize).checked_sub(1)
+}
+
+
+// The C interface, over 'CoreSolver' with the default settings.
+
+type CSolver = Ipasir<minisat::CoreSolver>;
+
+static SIGNATURE: &[u8] = b"minisat-rust 0.0.1\0";
+
+
+// Pointers passed by the user are assumed to be valid for the lifetime of the solver.
+struct CCallback<F> {
+    data: *mut c_void,
+    callback: F,
+}
+
+unsafe impl<F> Send for CCallback<F> {}
+unsafe impl<F> Sync for CCallback<F> {}
+
+
+#[no_mangle]
+pub extern "C" fn ipasir_signature() -> *const c_char {
+    SIGNATURE.as_ptr() as *const c_char
+}
+
+#[no_mangle]
+pub extern "C" fn ipasir_init() -> *mut c_void {
+    Box::into_raw(Box::new(CSolver::new(minisat::CoreSolver::new(Default::default())))) as *mut c_void
+}
+
+/// # Safety
+/// `solver` must come from `ipasir_init` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_release(solver: *mut c_void) {
+    drop(Box::from_raw(solver as *mut CSolver));
+}
+
+/// # Safety
+/// `solver` must come from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_add(solver: *mut c_void, lit_or_zero: i32) {
+    (*(solver as *mut CSolver)).add(lit_or_zero);
+}
+
+/// # Safety
+/// `solver` must come from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_assume(solver: *mut c_void, lit: i32) {
+    (*(solver as *mut CSolver)).assume(lit);
+}
+
+/// # Safety
+/// `solver` must come from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_solve(solver: *mut c_void) -> c_int {
+    (*(solver as *mut CSolver)).solve()
+}
+
+/// # Safety
+/// `solver` must come from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_val(solver: *mut c_void, lit: i32) -> i32 {
+    (*(solver as *const CSolver)).val(lit)
+}
+
+/// # Safety
+/// `solver` must come from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_failed(solver: *mut c_void, lit: i32) -> c_int {
+    (*(solver as *const CSolver)).failed(lit) as c_int
+}
+
+/// # Safety
+/// `solver` must come from `ipasir_init`, `terminate` (if not null) is called with `data`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_terminate(
+    solver: *mut c_void,
+    data: *mut c_void,
+    terminate: Option<unsafe extern "C" fn(*mut c_void) -> c_int>,
+) {
+    let terminate = terminate.map(|callback| {
+        let cb = CCallback { data, callback };
+        Box::new(move || (cb.callback)(cb.data) != 0) as Box<dyn Fn() -> bool + Send + Sync>
+    });
+    (*(solver as *mut CSolver)).set_terminate(terminate);
+}
+
+/// # Safety
+/// `solver` must come from `ipasir_init`, `learn` (if not null) is called with `data` and a
+/// zero-terminated clause valid during the call only.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_learn(
+    solver: *mut c_void,
+    data: *mut c_void,
+    max_length: c_int,
+    learn: Option<unsafe extern "C" fn(*mut c_void, *mut i32)>,
+) {
+    let learn = learn.map(|callback| {
+        let cb = CCallback { data, callback };
+        let mut buf = Vec::new();
+        Box::new(move |clause: &[i32]| {
+            buf.clear();
+            buf.extend_from_slice(clause);
+            buf.push(0);
+            (cb.callback)(cb.data, buf.as_mut_ptr());
+        }) as LearnCallback
+    });
+    (*(solver as *mut CSolver)).set_learn(max_length.max(0) as usize, learn);
+}
@@ -1,19 +1,29 @@
-use crate::sat::{SolveRes, Solver, Stats};
+use std::io;
+use crate::sat::{IncrementalRes, IncrementalSolver, SolveRes, Solver, Stats};
 use crate::sat::formula::{util, Lit, Var};
 use self::search::clause_db::ClauseDBSettings;
 pub use self::search::conflict::CCMinMode;
 use self::search::decision_heuristic::DecisionHeuristicSettings;
-pub use self::search::decision_heuristic::PhaseSaving;
+pub use self::search::decision_heuristic::{Branching, PhaseSaving};
+pub use self::handle::{Background, SolveHandle};
+pub use self::search::checkpoint::{CheckpointReader, CheckpointWriter};
+pub use self::search::{CallbackExchange, ClauseExchange, ExportCallback, RestartPolicy};
+pub use self::search::local_search::LocalSearchAlgorithm;
+pub use self::search::lookahead::CubeSettings;
+pub use self::search::observer::{Observer, Progress};
+pub use self::search::propagator::{ModelRefinement, Propagation, Propagator};
+pub use self::search::query::{Conflict, Implied};
 use self::search::*;
 use self::search::simplify::elim_clauses::*;
 use self::search::simplify::*;
 use self::budget::Budget;
 
 pub mod budget;
+mod handle;
 mod search;
 
 
-#[derive(Default)]
+#[derive(Clone, Copy, Default, Debug)]
 pub struct CoreSettings {
     pub heur: DecisionHeuristicSettings,
     pub db: ClauseDBSettings,
@@ -23,6 +33,19 @@ pub struct CoreSettings {
 }
 
 
+// Solvers whose state can be saved between two searches and restored into a new solver, e.g. to
+// continue a long run after a crash.
+pub trait Checkpoint: Solver {
+    fn checkpoint<W: io::Write>(&self, w: &mut CheckpointWriter<W>) -> io::Result<()>;
+
+    // Must be called on a new solver, with the settings of the checkpointed one.
+    fn resume<R: io::Read>(&mut self, r: &mut CheckpointReader<R>) -> io::Result<()>;
+}
+
+
+// Clones are independent solvers in the same state, without the callbacks set on the original
+// (clause exchange, observer, propagator and model refinement).
+#[derive(Clone)]
 pub struct CoreSolver {
     ok: bool, // If FALSE, the constraints are already unsatisfiable. No part of the solver state may be used!
     ss: SearchSettings,
@@ -87,6 +110,47 @@ impl Solver for CoreSolver {
     }
 }
 
+impl IncrementalSolver for CoreSolver {
+    fn solve_incremental(&mut self, budget: &Budget, assumptions: &[Lit]) -> IncrementalRes {
+        if !self.ok {
+            return IncrementalRes::UnSat(Vec::new());
+        }
+
+        match self.search.search_incremental(&self.ss, budget, assumptions) {
+            IncrementalSearchRes::UnSat => {
+                self.ok = false;
+                IncrementalRes::UnSat(Vec::new())
+            }
+
+            IncrementalSearchRes::Sat(model) => {
+                IncrementalRes::Sat(model.iter().map(|(v, s)| v.sign_lit(!*s)).collect())
+            }
+
+            IncrementalSearchRes::Failed(failed) => IncrementalRes::UnSat(failed),
+            IncrementalSearchRes::Interrupted(c) => IncrementalRes::Interrupted(c),
+        }
+    }
+}
+
+impl Checkpoint for CoreSolver {
+    fn checkpoint<W: io::Write>(&self, w: &mut CheckpointWriter<W>) -> io::Result<()> {
+        w.u64(self.ok as u64)?;
+        if self.ok {
+            self.search.write_checkpoint(w)?;
+        }
+        Ok(())
+    }
+
+    fn resume<R: io::Read>(&mut self, r: &mut CheckpointReader<R>) -> io::Result<()> {
+        if r.u64()? != 0 {
+            self.ok = self.search.read_checkpoint(r, &self.ss)?;
+        } else {
+            self.ok = false;
+        }
+        Ok(())
+    }
+}
+
 impl CoreSolver {
     pub fn new(settings: CoreSettings) -> Self {
         CoreSolver {
@@ -100,9 +164,67 @@ impl CoreSolver {
             ),
         }
     }
+
+    pub fn set_exchange(&mut self, exchange: Box<dyn ClauseExchange>) {
+        self.search.set_exchange(exchange);
+    }
+
+    pub fn set_observer(&mut self, observer: Box<dyn Observer>) {
+        self.search.set_observer(observer);
+    }
+
+    pub fn take_observer(&mut self) -> Option<Box<dyn Observer>> {
+        self.search.take_observer()
+    }
+
+    // Takes the external propagator into the search (see 'Propagator').
+    pub fn set_propagator(&mut self, propagator: Box<dyn Propagator>) {
+        self.search.set_propagator(propagator);
+    }
+
+    // Complete assignments are passed to 'refine' before they are reported, the clauses it returns
+    // are added and the search goes on (counterexample-guided abstraction refinement).
+    pub fn set_model_refinement(&mut self, refine: ModelRefinement) {
+        self.search.set_model_refinement(refine);
+    }
+
+    // Literals implied by unit propagation under the assumptions, or the conflict it runs into (see
+    // 'Searcher::propagate_assumptions'). Nothing is learnt, the solver stays at the ground level.
+    pub fn propagate_assumptions(&mut self, assumptions: &[Lit]) -> Result<Vec<Lit>, Conflict> {
+        self.explain_assumptions_with(assumptions, false)
+            .map(|implied| implied.into_iter().map(|(lit, _)| lit).collect())
+    }
+
+    // Same as 'propagate_assumptions', with the reason clause of every implied literal.
+    pub fn explain_assumptions(&mut self, assumptions: &[Lit]) -> Result<Vec<Implied>, Conflict> {
+        self.explain_assumptions_with(assumptions, true)
+    }
+
+    fn explain_assumptions_with(&mut self, assumptions: &[Lit], explain: bool) -> Result<Vec<Implied>, Conflict> {
+        if !self.ok {
+            return Err(Conflict { clause: Vec::new(), assumptions: Vec::new() });
+        }
+
+        let res = self.search.propagate_assumptions(assumptions, explain);
+        if let Err(ref conflict) = res {
+            // A conflict without assumptions is found at the ground level:
+            self.ok = !conflict.assumptions.is_empty();
+        }
+        res
+    }
+
+    // Splits the formula into cubes for cube-and-conquer (see 'Searcher::cube').
+    pub fn cube(&mut self, settings: &CubeSettings) -> Vec<Vec<Lit>> {
+        if self.ok {
+            self.search.cube(settings)
+        } else {
+            Vec::new()
+        }
+    }
 }
 
 
+#[derive(Clone, Copy, Debug)]
 pub struct SimpSettings {
     pub core: CoreSettings,
     pub simp: SimplificatorSettings,
@@ -120,6 +242,7 @@ impl Default for SimpSettings {
 }
 
 
+#[derive(Clone)]
 pub struct SimpSolver {
     core: CoreSolver,
     elimclauses: ElimClauses,
@@ -259,6 +382,66 @@ impl Solver for SimpSolver {
     }
 }
 
+// Simplification runs before the first incremental solve and is turned off afterwards, so the
+// variables used by later clauses must be frozen beforehand (see 'set_frozen'). Assumptions are
+// frozen here. Clauses over eliminated variables are not detected: they are added as is, and the
+// results are wrong. 'Ipasir' freezes every variable it creates.
+impl IncrementalSolver for SimpSolver {
+    fn freeze(&mut self, v: Var) {
+        self.set_frozen(v, true);
+    }
+
+    fn solve_incremental(&mut self, budget: &Budget, assumptions: &[Lit]) -> IncrementalRes {
+        if let Some(ref mut simp) = self.simp {
+            for lit in assumptions.iter() {
+                simp.set_frozen(&self.core.search, lit.var(), true);
+            }
+        }
+
+        if self.simp.is_some() && !self.preprocess(budget) {
+            return IncrementalRes::UnSat(Vec::new());
+        }
+
+        if !self.core.ok {
+            return IncrementalRes::UnSat(Vec::new());
+        }
+
+        match self.core.search.search_incremental(&self.core.ss, budget, assumptions) {
+            IncrementalSearchRes::UnSat => {
+                self.core.ok = false;
+                IncrementalRes::UnSat(Vec::new())
+            }
+
+            IncrementalSearchRes::Sat(mut model) => {
+                self.elimclauses.extend_model(&mut model);
+                IncrementalRes::Sat(model.iter().map(|(v, s)| v.sign_lit(!*s)).collect())
+            }
+
+            IncrementalSearchRes::Failed(failed) => IncrementalRes::UnSat(failed),
+            IncrementalSearchRes::Interrupted(c) => IncrementalRes::Interrupted(c),
+        }
+    }
+}
+
+// Only after the preprocessing, the state of the simplificator is not saved.
+impl Checkpoint for SimpSolver {
+    fn checkpoint<W: io::Write>(&self, w: &mut CheckpointWriter<W>) -> io::Result<()> {
+        if self.simp.is_some() {
+            return Err(io::Error::other("Checkpoint of a solver before the preprocessing"));
+        }
+
+        self.core.checkpoint(w)?;
+        self.elimclauses.write_checkpoint(w)
+    }
+
+    fn resume<R: io::Read>(&mut self, r: &mut CheckpointReader<R>) -> io::Result<()> {
+        self.simp_off();
+        self.core.resume(r)?;
+        let vars = self.core.n_vars();
+        self.elimclauses.read_checkpoint(r, vars)
+    }
+}
+
 impl SimpSolver {
     pub fn new(settings: SimpSettings) -> Self {
         let mut core = CoreSolver::new(settings.core);
@@ -270,6 +453,35 @@ impl SimpSolver {
         }
     }
 
+    pub fn set_exchange(&mut self, exchange: Box<dyn ClauseExchange>) {
+        self.core.set_exchange(exchange);
+    }
+
+    pub fn set_observer(&mut self, observer: Box<dyn Observer>) {
+        self.core.set_observer(observer);
+    }
+
+    pub fn take_observer(&mut self) -> Option<Box<dyn Observer>> {
+        self.core.take_observer()
+    }
+
+    // See 'CoreSolver::propagate_assumptions'. The assumptions must be frozen, like for incremental
+    // solving, and eliminated variables are never implied.
+    pub fn propagate_assumptions(&mut self, assumptions: &[Lit]) -> Result<Vec<Lit>, Conflict> {
+        self.core.propagate_assumptions(assumptions)
+    }
+
+    pub fn explain_assumptions(&mut self, assumptions: &[Lit]) -> Result<Vec<Implied>, Conflict> {
+        self.core.explain_assumptions(assumptions)
+    }
+
+    // Frozen variables are never eliminated. Has no effect once the simplification is turned off.
+    pub fn set_frozen(&mut self, v: Var, frozen: bool) {
+        if let Some(ref mut simp) = self.simp {
+            simp.set_frozen(&self.core.search, v, frozen);
+        }
+    }
+
     fn simp_off(&mut self) {
         if let Some(_) = self.simp {
             Simplificator::off(&mut self.core.search);
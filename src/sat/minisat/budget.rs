@@ -1,11 +1,17 @@
 use std::sync::atomic;
 
 
+// Number of 'within' and 'interrupted' calls between the polls of 'terminate', which may be costly
+// (e.g. a C callback).
+const TERMINATE_POLL: u64 = 1 << 10;
+
 // Resource contraints:
 pub struct Budget {
     conflict_budget: i64,    // -1 means no budget.
     propagation_budget: i64, // -1 means no budget.
     asynch_interrupt: atomic::AtomicBool,
+    terminate: Option<Box<dyn Fn() -> bool + Send + Sync>>, // Polled by the solver, interrupts it when returns TRUE.
+    checks: atomic::AtomicU64, // Number of 'within' and 'interrupted' calls.
 }
 
 impl Budget {
@@ -14,17 +20,59 @@ impl Budget {
             conflict_budget: -1,
             propagation_budget: -1,
             asynch_interrupt: atomic::AtomicBool::new(false),
+            terminate: None,
+            checks: atomic::AtomicU64::new(0),
         }
     }
 
+    // Called by the search loop on every iteration.
     pub fn within(&self, conflicts: u64, propagations: u64) -> bool {
-        !self.asynch_interrupt.load(atomic::Ordering::Relaxed)
+        !self.interrupted()
             && (self.conflict_budget < 0 || conflicts < self.conflict_budget as u64)
             && (self.propagation_budget < 0 || propagations < self.propagation_budget as u64)
     }
 
+    // Polls 'terminate' on every 'TERMINATE_POLL'-th call only (counting 'within' too), so it can be
+    // called in tight loops.
     pub fn interrupted(&self) -> bool {
-        self.asynch_interrupt.load(atomic::Ordering::Relaxed)
+        let poll = self.checks.fetch_add(1, atomic::Ordering::Relaxed).is_multiple_of(TERMINATE_POLL);
+        self.asynch_interrupt.load(atomic::Ordering::Relaxed) || (poll && self.terminated())
+    }
+
+    // Polls 'terminate' on every call, for callers checking on a schedule of their own.
+    pub fn interrupted_now(&self) -> bool {
+        self.asynch_interrupt.load(atomic::Ordering::Relaxed) || self.terminated()
+    }
+
+    fn terminated(&self) -> bool {
+        match self.terminate {
+            Some(ref terminate) => terminate(),
+            None => false,
+        }
+    }
+
+    pub fn interrupt(&self) {
+        self.asynch_interrupt.store(true, atomic::Ordering::Relaxed);
+    }
+
+    pub fn set_terminate(&mut self, terminate: Option<Box<dyn Fn() -> bool + Send + Sync>>) {
+        self.terminate = terminate;
+    }
+
+    // The search is interrupted once the solver has 'conflicts' conflicts in total.
+    pub fn set_conflict_limit(&mut self, conflicts: u64) {
+        self.conflict_budget = conflicts as i64;
+    }
+
+    // Budget of one of 'parts' solvers sharing the limits of this one, 'conflicts' and 'propagations'
+    // of which are used already. The solvers start from scratch, interrupts are not shared.
+    pub fn share(&self, parts: u64, conflicts: u64, propagations: u64) -> Budget {
+        let share = |budget: i64, used: u64| if budget < 0 { -1 } else { (budget as u64).saturating_sub(used).div_ceil(parts) as i64 };
+        Budget {
+            conflict_budget: share(self.conflict_budget, conflicts),
+            propagation_budget: share(self.propagation_budget, propagations),
+            ..Budget::new()
+        }
     }
 
     pub fn off(&mut self) {
@@ -0,0 +1,160 @@
+use std::{panic, thread};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::sat::{IncrementalRes, IncrementalSolver};
+use crate::sat::formula::Lit;
+use super::budget::Budget;
+use super::{CoreSolver, Observer, Progress, SimpSolver};
+
+
+// Solvers that 'SolveHandle' can run in a worker thread.
+pub trait Background: IncrementalSolver + Send + 'static {
+    fn set_observer(&mut self, observer: Box<dyn Observer>);
+    fn take_observer(&mut self) -> Option<Box<dyn Observer>>;
+}
+
+impl Background for CoreSolver {
+    fn set_observer(&mut self, observer: Box<dyn Observer>) {
+        CoreSolver::set_observer(self, observer);
+    }
+
+    fn take_observer(&mut self) -> Option<Box<dyn Observer>> {
+        CoreSolver::take_observer(self)
+    }
+}
+
+impl Background for SimpSolver {
+    fn set_observer(&mut self, observer: Box<dyn Observer>) {
+        SimpSolver::set_observer(self, observer);
+    }
+
+    fn take_observer(&mut self) -> Option<Box<dyn Observer>> {
+        SimpSolver::take_observer(self)
+    }
+}
+
+
+// State of the search published by the worker.
+#[derive(Default)]
+struct Live {
+    conflicts: AtomicU64, // Conflicts counted by the events, between two snapshots too.
+    snapshot: Mutex<(Progress, u64)>, // Last snapshot, with 'conflicts' at the time of it.
+    observer: Mutex<Option<Box<dyn Observer>>>, // Observer of the user, once the search is over.
+}
+
+
+// Publishes the events of the search, then passes them to the observer of the user.
+struct Publisher {
+    live: Arc<Live>,
+    inner: Option<Box<dyn Observer>>,
+}
+
+impl Publisher {
+    fn publish(&self, progress: &Progress) {
+        *self.live.snapshot.lock().unwrap() = (*progress, self.live.conflicts.load(Ordering::Relaxed));
+    }
+}
+
+impl Observer for Publisher {
+    fn restarted(&mut self, progress: &Progress) {
+        self.publish(progress);
+        if let Some(ref mut inner) = self.inner {
+            inner.restarted(progress);
+        }
+    }
+
+    fn conflict(&mut self, len: usize, lbd: u32) {
+        self.live.conflicts.fetch_add(1, Ordering::Relaxed);
+        if let Some(ref mut inner) = self.inner {
+            inner.conflict(len, lbd);
+        }
+    }
+
+    fn reduced(&mut self, before: usize, after: usize) {
+        if let Some(ref mut inner) = self.inner {
+            inner.reduced(before, after);
+        }
+    }
+
+    fn garbage_collected(&mut self, before: usize, after: usize) {
+        if let Some(ref mut inner) = self.inner {
+            inner.garbage_collected(before, after);
+        }
+    }
+
+    fn simplified(&mut self, removed: usize) {
+        if let Some(ref mut inner) = self.inner {
+            inner.simplified(removed);
+        }
+    }
+
+    fn progress(&mut self, progress: &Progress) {
+        self.publish(progress);
+        if let Some(ref mut inner) = self.inner {
+            inner.progress(progress);
+        }
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        *self.live.observer.lock().unwrap() = self.inner.take();
+    }
+}
+
+
+// A solver searching in a worker thread (see 'IncrementalSolver::solve_incremental'). The search can
+// be watched and interrupted while it runs, 'join' gives the solver back with the result, ready for
+// the next solve. An observer set on the solver still receives the events of the search.
+pub struct SolveHandle<S> {
+    budget: Arc<Budget>,
+    live: Arc<Live>,
+    worker: thread::JoinHandle<(S, IncrementalRes)>,
+}
+
+impl<S: Background> SolveHandle<S> {
+    pub fn spawn(mut solver: S, budget: Budget, assumptions: Vec<Lit>) -> Self {
+        let budget = Arc::new(budget);
+        let live = Arc::new(Live::default());
+        let inner = solver.take_observer();
+        solver.set_observer(Box::new(Publisher { live: live.clone(), inner }));
+
+        let worker = {
+            let budget = budget.clone();
+            let live = live.clone();
+            thread::spawn(move || {
+                let res = solver.solve_incremental(&budget, &assumptions);
+                drop(solver.take_observer());
+                if let Some(observer) = live.observer.lock().unwrap().take() {
+                    solver.set_observer(observer);
+                }
+                (solver, res)
+            })
+        };
+
+        SolveHandle { budget, live, worker }
+    }
+
+    // Snapshot of the search, taken at the last restart or line of the statistics table. Only the
+    // number of conflicts is up to date.
+    pub fn progress(&self) -> Progress {
+        let (mut progress, counted) = *self.live.snapshot.lock().unwrap();
+        progress.conflicts += self.live.conflicts.load(Ordering::Relaxed) - counted;
+        progress
+    }
+
+    // Interrupts the search through the budget, the result will be 'Interrupted' unless it is
+    // already known.
+    pub fn interrupt(&self) {
+        self.budget.interrupt();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.worker.is_finished()
+    }
+
+    // Waits for the result. A panic of the worker is passed on.
+    pub fn join(self) -> (S, IncrementalRes) {
+        self.worker.join().unwrap_or_else(|err| panic::resume_unwind(err))
+    }
+}
@@ -1,8 +1,14 @@
+use std::sync::mpsc;
 use crate::sat;
-use crate::sat::formula::{assignment::*, clause::*, LBool, Lit, LitMap, Var};
+use crate::sat::formula::{assignment::*, clause::*, util::*, LBool, Lit, LitMap, Var, VarMap};
 use self::backtrack::BacktrackableFormula;
 use self::conflict::{AnalyzeContext, CCMinMode, Conflict};
-use self::decision_heuristic::{DecisionHeuristic, DecisionHeuristicSettings};
+use self::decision_heuristic::{DecisionHeuristic, DecisionHeuristicSettings, Rephase};
+use self::local_search::{LocalSearch, LocalSearchAlgorithm};
+use self::observer::Observer;
+use self::propagator::{lazy_reason, External, ModelCheck, ModelRefinement};
+use self::random::Random;
+use self::restart::RestartGuard;
 use self::util::*;
 use super::budget::Budget;
 
@@ -10,26 +16,55 @@ mod backtrack;
 pub mod conflict;
 pub mod clause_db;
 pub mod decision_heuristic;
+pub mod local_search;
+pub mod checkpoint;
+pub mod lookahead;
+pub mod observer;
+pub mod propagator;
+pub mod query;
 mod luby;
 pub mod simplify;
 mod random;
+mod restart;
 mod util;
 mod watches;
 
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RestartPolicy {
+    Static,  // Luby or geometric sequence of restart limits.
+    Glucose, // Average LBD of a window of recent conflicts compared to the overall average.
+    Ema,     // Fast and slow exponential moving averages of LBD.
+}
+
+
 #[derive(Clone, Copy, Debug)]
 pub struct RestartStrategy {
+    pub policy: RestartPolicy,
     pub luby_restart: bool,
     pub restart_first: f64, // The initial restart limit.
     pub restart_inc: f64, // The factor with which the restart limit is multiplied in each restart.
+    pub lbd_window: usize, // Number of conflicts for the fast LBD average (also the minimal restart interval).
+    pub lbd_margin: f64, // Restart if the fast LBD average exceeds the slow one by this factor.
+    pub slow_alpha: f64, // Smoothing factor of the slow LBD average (EMA policy).
+    pub trail_window: usize, // Number of conflicts for the trail size average.
+    pub blocking: Option<f64>, // Block restarts if the trail is larger than its average by this factor.
+    pub block_min_conflicts: u64, // Don't block restarts before this number of conflicts.
 }
 
 impl Default for RestartStrategy {
     fn default() -> Self {
         RestartStrategy {
+            policy: RestartPolicy::Static,
             luby_restart: true,
             restart_first: 100.0,
             restart_inc: 2.0,
+            lbd_window: 50,
+            lbd_margin: 1.25,
+            slow_alpha: 1e-5,
+            trail_window: 5000,
+            blocking: Some(1.4),
+            block_min_conflicts: 10000,
         }
     }
 }
@@ -47,6 +82,66 @@ impl RestartStrategy {
 }
 
 
+#[derive(Clone, Copy, Debug)]
+pub struct ModeStrategy {
+    pub switching: bool, // Alternate between the focused and the stable mode.
+    pub init_conflicts: u64, // Length of the first focused phase in conflicts.
+    pub factor: f64, // Phase lengths (measured in propagations) are multiplied by this factor after each stable phase.
+    pub stable_restart: RestartStrategy, // Restarts in the stable mode ('SearchSettings::restart' is used in the focused one).
+}
+
+impl Default for ModeStrategy {
+    fn default() -> Self {
+        ModeStrategy {
+            switching: false,
+            init_conflicts: 1000,
+            factor: 2.0,
+            stable_restart: RestartStrategy { restart_first: 1024.0, ..Default::default() },
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug)]
+pub struct RephaseStrategy {
+    pub enabled: bool, // Periodically reset saved phases.
+    pub interval: u64, // Conflicts between the first two rephasings, growing arithmetically.
+}
+
+impl Default for RephaseStrategy {
+    fn default() -> Self {
+        RephaseStrategy {
+            enabled: false,
+            interval: 1000,
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug)]
+pub struct LocalSearchStrategy {
+    pub algorithm: LocalSearchAlgorithm,
+    pub standalone: bool, // Only run local search (at most 'max_flips' flips), without CDCL.
+    pub max_flips: u64,
+    pub walk: bool, // Seed saved phases with local search when rephasing.
+    pub effort: f64, // Flips per rephasing walk, relative to propagations since the previous one.
+    pub random_seed: f64,
+}
+
+impl Default for LocalSearchStrategy {
+    fn default() -> Self {
+        LocalSearchStrategy {
+            algorithm: LocalSearchAlgorithm::ProbSat,
+            standalone: false,
+            max_flips: 100_000_000,
+            walk: false,
+            effort: 0.1,
+            random_seed: 91648253.0,
+        }
+    }
+}
+
+
 #[derive(Clone, Copy, Debug)]
 pub struct LearningStrategy {
     pub min_learnts_lim: i32, // Minimum number to set the learnts limit to.
@@ -69,6 +164,29 @@ impl Default for LearningStrategy {
 }
 
 
+#[derive(Clone, Copy, Debug)]
+pub struct VivifyStrategy {
+    pub learnts: bool, // Shorten learnt clauses by vivification.
+    pub clauses: bool, // Shorten problem clauses by vivification.
+    pub interval: u64, // Minimal number of conflicts between two vivification rounds.
+    pub max_lbd: u32, // Only learnt clauses with at most this LBD are vivified.
+    pub effort: f64, // Propagations allowed per round, relative to propagations made since the previous one.
+}
+
+impl Default for VivifyStrategy {
+    fn default() -> Self {
+        VivifyStrategy {
+            learnts: false,
+            clauses: false,
+            interval: 2000,
+            max_lbd: 8,
+            effort: 0.1,
+        }
+    }
+}
+
+
+#[derive(Clone)]
 struct LearningGuard {
     settings: LearningStrategy,
     max_learnts: f64,
@@ -111,6 +229,7 @@ impl LearningGuard {
 }
 
 
+#[derive(Clone)]
 struct SimplifyGuard {
     simp_db_assigns: Option<usize>, // Number of top-level assignments since last execution of 'simplify()'.
     simp_db_props: u64,
@@ -135,12 +254,264 @@ impl SimplifyGuard {
 }
 
 
-#[derive(Default)]
+#[derive(Clone)]
+struct VivifyGuard {
+    settings: VivifyStrategy,
+    next_conflicts: u64,
+    last_propagations: u64,
+}
+
+impl VivifyGuard {
+    pub fn new(settings: VivifyStrategy, conflicts: u64, propagations: u64) -> Self {
+        VivifyGuard {
+            settings,
+            next_conflicts: conflicts + settings.interval,
+            last_propagations: propagations,
+        }
+    }
+
+    // Returns the propagation limit for the next round, if it's time to run one.
+    pub fn start(&mut self, conflicts: u64, propagations: u64) -> Option<u64> {
+        if !(self.settings.learnts || self.settings.clauses) || conflicts < self.next_conflicts {
+            return None;
+        }
+
+        let budget = ((propagations - self.last_propagations) as f64 * self.settings.effort) as u64;
+        self.next_conflicts = conflicts + self.settings.interval;
+        self.last_propagations = propagations;
+        Some(propagations + budget)
+    }
+}
+
+
+// Decides when to switch between the focused and the stable mode. The first focused phase is limited
+// by conflicts, the following ones by the number of propagations made in it.
+#[derive(Clone)]
+struct ModeGuard {
+    settings: ModeStrategy,
+    stable: bool,
+    conflicts_limit: Option<u64>,
+    phase_propagations: u64,
+    start_propagations: u64,
+}
+
+impl ModeGuard {
+    pub fn new(settings: ModeStrategy, conflicts: u64, propagations: u64) -> Self {
+        ModeGuard {
+            settings,
+            stable: false,
+            conflicts_limit: Some(conflicts + settings.init_conflicts),
+            phase_propagations: 0,
+            start_propagations: propagations,
+        }
+    }
+
+    pub fn should_switch(&self, conflicts: u64, propagations: u64) -> bool {
+        if !self.settings.switching {
+            return false;
+        }
+
+        match self.conflicts_limit {
+            Some(limit) => conflicts >= limit,
+            None => propagations - self.start_propagations >= self.phase_propagations,
+        }
+    }
+
+    pub fn switch(&mut self, propagations: u64) {
+        if self.conflicts_limit.take().is_some() {
+            self.phase_propagations = propagations - self.start_propagations;
+        } else if self.stable {
+            self.phase_propagations = (self.phase_propagations as f64 * self.settings.factor) as u64;
+        }
+
+        self.stable = !self.stable;
+        self.start_propagations = propagations;
+    }
+}
+
+
+#[derive(Clone, Copy)]
+enum RephaseStep {
+    Reset(Rephase),
+    Walk, // Replace saved phases with the best assignment found by local search from them.
+}
+
+const REPHASE_CYCLE: [RephaseStep; 6] = [
+    RephaseStep::Reset(Rephase::Best),
+    RephaseStep::Reset(Rephase::Original),
+    RephaseStep::Reset(Rephase::Best),
+    RephaseStep::Reset(Rephase::Inverted),
+    RephaseStep::Reset(Rephase::Best),
+    RephaseStep::Reset(Rephase::Random),
+];
+
+const REPHASE_WALK_CYCLE: [RephaseStep; 8] = [
+    RephaseStep::Reset(Rephase::Best),
+    RephaseStep::Walk,
+    RephaseStep::Reset(Rephase::Original),
+    RephaseStep::Reset(Rephase::Best),
+    RephaseStep::Walk,
+    RephaseStep::Reset(Rephase::Inverted),
+    RephaseStep::Reset(Rephase::Best),
+    RephaseStep::Reset(Rephase::Random),
+];
+
+#[derive(Clone)]
+struct RephaseGuard {
+    settings: RephaseStrategy,
+    cycle: &'static [RephaseStep],
+    rephases: u64,
+    next_conflicts: u64,
+}
+
+impl RephaseGuard {
+    pub fn new(settings: RephaseStrategy, walk: bool, conflicts: u64) -> Self {
+        RephaseGuard {
+            settings,
+            cycle: if walk { &REPHASE_WALK_CYCLE } else { &REPHASE_CYCLE },
+            rephases: 0,
+            next_conflicts: conflicts + settings.interval,
+        }
+    }
+
+    pub fn start(&mut self, conflicts: u64) -> Option<RephaseStep> {
+        if !self.settings.enabled || conflicts < self.next_conflicts {
+            return None;
+        }
+
+        let step = self.cycle[(self.rephases % self.cycle.len() as u64) as usize];
+        self.rephases += 1;
+        self.next_conflicts = conflicts + self.settings.interval * (self.rephases + 1);
+        Some(step)
+    }
+}
+
+
+#[derive(Clone)]
+struct WalkGuard {
+    settings: LocalSearchStrategy,
+    rand: Random,
+    last_propagations: u64,
+}
+
+impl WalkGuard {
+    pub fn new(settings: LocalSearchStrategy, propagations: u64) -> Self {
+        WalkGuard {
+            settings,
+            rand: Random::new(settings.random_seed),
+            last_propagations: propagations,
+        }
+    }
+
+    // Returns the flip limit for the next walk.
+    pub fn start(&mut self, propagations: u64) -> u64 {
+        let flips = ((propagations - self.last_propagations) as f64 * self.settings.effort) as u64;
+        self.last_propagations = propagations;
+        flips
+    }
+}
+
+
+// The state of a search across its restarts: when to restart, reduce the learnt clauses, switch the
+// mode, rephase and vivify. An interrupted search keeps it, so that the next search under the same
+// assumptions continues the schedule instead of starting over.
+#[derive(Clone)]
+struct Schedule {
+    assumptions: Vec<Lit>,
+    learnt: LearningGuard,
+    vivify: VivifyGuard,
+    restarts: [RestartGuard; 2], // Of the focused and of the stable mode.
+    mode: ModeGuard,
+    rephase: RephaseGuard,
+    walk: WalkGuard,
+}
+
+impl Schedule {
+    fn new(ss: &SearchSettings, assumptions: &[Lit], clauses: usize, conflicts: u64, propagations: u64) -> Self {
+        let mut learnt = LearningGuard::new(ss.learn);
+        learnt.reset(clauses);
+        Schedule {
+            assumptions: assumptions.to_vec(),
+            learnt,
+            vivify: VivifyGuard::new(ss.vivify, conflicts, propagations),
+            restarts: [RestartGuard::new(ss.restart), RestartGuard::new(ss.mode.stable_restart)],
+            mode: ModeGuard::new(ss.mode, conflicts, propagations),
+            rephase: RephaseGuard::new(ss.rephase, ss.local_search.walk, conflicts),
+            walk: WalkGuard::new(ss.local_search, propagations),
+        }
+    }
+}
+
+
+// Receives the clauses learnt by the search (with their LBD) and supplies clauses learnt elsewhere,
+// e.g. by other solvers working on the same formula. Imported clauses must be implied by the formula.
+pub trait ClauseExchange: Send {
+    fn export(&mut self, clause: &[Lit], lbd: u32);
+    fn import(&mut self) -> Vec<Vec<Lit>>;
+}
+
+
+// Called with a learnt clause and its LBD.
+pub type ExportCallback = Box<dyn FnMut(&[Lit], u32) + Send>;
+
+// Passes the learnt clauses up to the length and LBD limits to a callback and imports the clauses
+// received on a channel (at restarts).
+pub struct CallbackExchange {
+    max_len: usize,
+    max_lbd: u32,
+    export: Option<ExportCallback>,
+    import: Option<mpsc::Receiver<Vec<Lit>>>,
+}
+
+impl CallbackExchange {
+    pub fn new(max_len: usize, max_lbd: u32, export: Option<ExportCallback>, import: Option<mpsc::Receiver<Vec<Lit>>>) -> Self {
+        CallbackExchange { max_len, max_lbd, export, import }
+    }
+}
+
+impl ClauseExchange for CallbackExchange {
+    fn export(&mut self, clause: &[Lit], lbd: u32) {
+        if clause.len() <= self.max_len && lbd <= self.max_lbd {
+            if let Some(ref mut export) = self.export {
+                export(clause, lbd);
+            }
+        }
+    }
+
+    fn import(&mut self) -> Vec<Vec<Lit>> {
+        match self.import {
+            Some(ref import) => import.try_iter().collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+
+#[derive(Clone, Default)]
 struct Stats {
     solves: u64,
     starts: u64,
     decisions: u64,
     conflicts: u64,
+    chrono_backtracks: u64,
+    blocked_restarts: u64,
+    mode_switches: u64,
+    rephases: u64,
+    walk_flips: u64,
+    imported_clauses: u64,
+    vivified_clauses: u64,
+    vivified_literals: u64,
+    learnts: u64,
+    learnt_lbds: u64,
+    conflict_trails: u64,
+    reductions: u64,
+    garbage_collections: u64,
+    eliminated_vars: u64, // Published by the simplificator.
+    asymm_lits: u64,
+    equiv_vars: u64, // Published by the simplificator.
+    blocked_clauses: u64, // Published by the simplificator.
+    added_vars: u64, // Published by the simplificator.
+    gates: u64, // Published by the simplificator.
 }
 
 
@@ -148,6 +519,10 @@ struct Stats {
 pub struct SearchSettings {
     pub restart: RestartStrategy,
     pub learn: LearningStrategy,
+    pub vivify: VivifyStrategy,
+    pub mode: ModeStrategy,
+    pub rephase: RephaseStrategy,
+    pub local_search: LocalSearchStrategy,
 }
 
 
@@ -165,9 +540,20 @@ pub enum SearchRes {
 }
 
 
+// Result of a search that keeps the searcher (at the ground level) for further searches.
+pub enum IncrementalSearchRes {
+    UnSat,
+    Sat(VarMap<bool>),
+    Failed(Vec<Lit>), // The assumptions that led to a conflict.
+    Interrupted(f64),
+}
+
+
+#[derive(Clone, Copy, Debug)]
 pub struct SearcherSettings {
     pub garbage_frac: f64, // The fraction of wasted memory allowed before a garbage collection is triggered.
     pub use_rcheck: bool, // Check if a clause is already implied. Prett costly, and subsumes subsumptions :)
+    pub chrono: Option<usize>, // Backtrack chronologically if a backjump would undo more than this number of levels.
 }
 
 impl Default for SearcherSettings {
@@ -175,6 +561,7 @@ impl Default for SearcherSettings {
         SearcherSettings {
             garbage_frac: 0.20,
             use_rcheck: false,
+            chrono: None,
         }
     }
 }
@@ -195,6 +582,27 @@ pub struct SearchCtx {
     heur: DecisionHeuristic,
     analyze: AnalyzeContext,
     simp: SimplifyGuard,
+    exchange: Option<Box<dyn ClauseExchange>>,
+    external: Option<External>,
+    refine: Option<ModelRefinement>,
+    observer: Option<Box<dyn Observer>>,
+}
+
+// The callbacks of the user are not cloned.
+impl Clone for SearchCtx {
+    fn clone(&self) -> Self {
+        SearchCtx {
+            stats: self.stats.clone(),
+            db: self.db.clone(),
+            heur: self.heur.clone(),
+            analyze: self.analyze.clone(),
+            simp: self.simp.clone(),
+            exchange: None,
+            external: None,
+            refine: None,
+            observer: None,
+        }
+    }
 }
 
 impl SearchCtx {
@@ -205,6 +613,10 @@ impl SearchCtx {
             heur: DecisionHeuristic::new(heur_set),
             analyze: AnalyzeContext::new(ccmin_mode),
             simp: SimplifyGuard::new(),
+            exchange: None,
+            external: None,
+            refine: None,
+            observer: None,
         }
     }
 
@@ -213,7 +625,7 @@ impl SearchCtx {
         self.analyze.init_var(v);
     }
 
-    pub fn decide(&mut self, assigns: &mut Assignment, ca: &ClauseAllocator, assumptions: &[Lit]) -> Result<Option<Lit>, LitMap<()>> {
+    pub fn decide(&mut self, assigns: &mut Assignment, ca: &mut ClauseAllocator, assumptions: &[Lit]) -> Result<Option<Lit>, LitMap<()>> {
         while let Some(&p) = assumptions.get(assigns.current_level().offset_from_ground()) {
             // Perform user provided assumption:
             match assigns.of_lit(p) {
@@ -222,7 +634,8 @@ impl SearchCtx {
                     assigns.new_decision_level();
                 }
                 LBool::False => {
-                    let conflict = self.analyze.analyze_final(ca, assigns, !p);
+                    let external = &mut self.external;
+                    let conflict = self.analyze.analyze_final(ca, assigns, !p, |ca, lit| lazy_reason(external, ca, assigns, lit));
                     return Err(conflict);
                 }
                 LBool::Undef => {
@@ -233,6 +646,7 @@ impl SearchCtx {
 
         // New variable decision:
         self.stats.decisions += 1;
+        self.heur.sync_assigned(assigns);
         Ok(self.heur.pick_branch_lit(assigns))
     }
 
@@ -246,44 +660,130 @@ impl SearchCtx {
             {
                 let db = &mut self.db;
                 move |ca, c| db.bump_activity(ca, c)
+            },
+            {
+                let external = &mut self.external;
+                move |ca, lit| lazy_reason(external, ca, assigns, lit)
             }
         )
     }
 
-    fn cancel_until(&mut self, assigns: &Assignment, level: DecisionLevel) {
+    fn cancel_until(&mut self, assigns: &Assignment, target_level: DecisionLevel) {
+        self.heur.sync_assigned(assigns);
+        if let Some(ref mut external) = self.external {
+            external.backtrack(assigns, target_level);
+        }
+
         let top_level = assigns.current_level();
-        for (level, trail) in assigns.levels_above_rev(level) {
+        let mut unassigned = 0;
+        for (level, trail) in assigns.levels_above_rev(target_level) {
             for &lit in trail.iter().rev() {
-                self.heur.save_phase(lit, level == top_level);
-                self.heur.try_return_var(lit.var());
+                // Literals implied out of order at lower levels are kept (chronological backtracking):
+                if assigns.level(lit.var()) > target_level {
+                    self.heur.save_phase(lit, level == top_level);
+                    self.heur.try_return_var(lit.var());
+                    unassigned += 1;
+                }
+            }
+        }
+
+        self.heur.backtrack_to(assigns.trail().len() - unassigned);
+    }
+
+    // Passes the conflict-free part of the trail to the heuristic: everything below the current level
+    // on a conflict, or the whole trail otherwise.
+    fn update_phases(&mut self, assigns: &Assignment, conflict: bool) {
+        let trail = assigns.trail();
+        let len =
+            if !conflict {
+                trail.len()
+            } else if assigns.is_ground_level() {
+                return;
+            } else {
+                trail.len() - assigns.trail_above(assigns.current_level().previous()).len()
+            };
+
+        self.heur.update_phases(&trail[..len]);
+    }
+
+    // Chooses the level to backtrack to after learning a clause asserting at 'level'.
+    fn backtrack_level(&mut self, assigns: &Assignment, level: DecisionLevel, chrono: Option<usize>) -> DecisionLevel {
+        let current = assigns.current_level();
+        match chrono {
+            Some(limit) if current.offset_from_ground() - level.offset_from_ground() > limit => {
+                self.stats.chrono_backtracks += 1;
+                current.previous()
             }
+
+            _ => level,
         }
     }
 
-    fn handle_conflict(&mut self, learnt: &mut LearningGuard, bt: &mut BacktrackableFormula, confl: ClauseRef)
-        -> Option<(DecisionLevel, Lit, Option<ClauseRef>)>
+    // Returns the level to backtrack to and the asserting literal with its level and reason.
+    fn handle_conflict(
+        &mut self,
+        learnt: &mut LearningGuard,
+        restart: &mut RestartGuard,
+        bt: &mut BacktrackableFormula,
+        confl: ClauseRef,
+        chrono: Option<usize>,
+    ) -> Option<(DecisionLevel, Lit, DecisionLevel, Option<ClauseRef>)>
     {
         self.stats.conflicts += 1;
+        self.heur.sync_assigned(&bt.assigns);
+        let trail = bt.assigns.number_of_assigns();
+        self.stats.conflict_trails += trail as u64;
+
+        let conflict = self.analyze(&bt.assigns, &mut bt.ca, confl);
+        self.adopt_reasons(bt);
+        self.update_phases(&bt.assigns, true);
+
+        // Decay before backtracking, so that the heuristic sees bumped variables still assigned:
+        self.heur.decay_activity();
 
         let res =
-            match self.analyze(&bt.assigns, &mut bt.ca, confl) {
+            match conflict {
                 Conflict::Ground => {
                     return None;
                 }
 
                 Conflict::Unit(level, unit) => {
-                    self.cancel_until(&bt.assigns, level);
-                    (level, unit, None)
+                    if let Some(ref mut exchange) = self.exchange {
+                        exchange.export(&[unit], 1);
+                    }
+                    if let Some(ref mut observer) = self.observer {
+                        observer.conflict(1, 1);
+                    }
+                    self.stats.learnts += 1;
+                    self.stats.learnt_lbds += 1;
+                    if restart.conflict(self.stats.conflicts, 1, trail) {
+                        self.stats.blocked_restarts += 1;
+                    }
+                    let target = self.backtrack_level(&bt.assigns, level, chrono);
+                    self.cancel_until(&bt.assigns, target);
+                    (target, unit, level, None)
                 }
 
                 Conflict::Learned(level, lit, clause) => {
-                    self.cancel_until(&bt.assigns, level);
-                    let cr = self.db.learn_clause(&mut bt.ca, &clause[..]);
-                    (level, lit, Some(cr))
+                    let lbd = literal_block_distance(&bt.assigns, &clause[..]);
+                    if let Some(ref mut exchange) = self.exchange {
+                        exchange.export(&clause[..], lbd);
+                    }
+                    if let Some(ref mut observer) = self.observer {
+                        observer.conflict(clause.len(), lbd);
+                    }
+                    self.stats.learnts += 1;
+                    self.stats.learnt_lbds += lbd as u64;
+                    if restart.conflict(self.stats.conflicts, lbd, trail) {
+                        self.stats.blocked_restarts += 1;
+                    }
+                    let target = self.backtrack_level(&bt.assigns, level, chrono);
+                    self.cancel_until(&bt.assigns, target);
+                    let cr = self.db.learn_clause(&mut bt.ca, &clause[..], lbd);
+                    (target, lit, level, Some(cr))
                 }
             };
 
-        self.heur.decay_activity();
         self.db.decay_activity();
 
         if learnt.bump() {
@@ -298,6 +798,11 @@ impl SearchCtx {
                 (self.db.stats.learnts_literals as f64) / (self.db.stats.num_learnts as f64),
                 progress_estimate(&bt.assigns) * 100.0
             );
+
+            if let Some(mut observer) = self.observer.take() {
+                observer.progress(&self.progress(bt));
+                self.observer = Some(observer);
+            }
         }
 
         Some(res)
@@ -305,10 +810,12 @@ impl SearchCtx {
 }
 
 
+#[derive(Clone)]
 pub struct Searcher {
     settings: SearcherSettings,
     bt: backtrack::BacktrackableFormula,
-    ctx: SearchCtx
+    ctx: SearchCtx,
+    schedule: Option<Schedule>, // Of the interrupted search.
 }
 
 impl Searcher {
@@ -321,7 +828,8 @@ impl Searcher {
         Searcher {
             settings,
             bt: backtrack::BacktrackableFormula::new(),
-            ctx: SearchCtx::new(db_set, heur_set, ccmin_mode)
+            ctx: SearchCtx::new(db_set, heur_set, ccmin_mode),
+            schedule: None,
         }
     }
 
@@ -339,6 +847,18 @@ impl Searcher {
         v
     }
 
+    pub fn set_exchange(&mut self, exchange: Box<dyn ClauseExchange>) {
+        self.ctx.exchange = Some(exchange);
+    }
+
+    pub fn set_observer(&mut self, observer: Box<dyn Observer>) {
+        self.ctx.observer = Some(observer);
+    }
+
+    pub fn take_observer(&mut self) -> Option<Box<dyn Observer>> {
+        self.ctx.observer.take()
+    }
+
     pub fn add_clause(&mut self, clause: &[Lit]) -> AddClauseRes {
         // TODO: it should be here to work identical to original MiniSat. Probably not the best place.
         if self.settings.use_rcheck && is_implied(&mut self.bt, &mut self.ctx.heur, &clause) {
@@ -394,56 +914,146 @@ impl Searcher {
         }
     }
 
-    pub fn search(self, ss: &SearchSettings, budget: &Budget, assumptions: &[Lit]) -> SearchRes {
+    pub fn search(mut self, ss: &SearchSettings, budget: &Budget, assumptions: &[Lit]) -> SearchRes {
+        match self.search_internal(ss, budget, assumptions) {
+            LoopRes::SAT => {
+                let stats = self.stats();
+                SearchRes::SAT(self.bt.assigns, stats)
+            }
+
+            LoopRes::AssumpsConfl(_) => {
+                // TODO: implement properly
+                self.cancel_until(GROUND_LEVEL);
+                SearchRes::UnSAT(self.stats())
+            }
+
+            LoopRes::Interrupted(c) => SearchRes::Interrupted(c, self),
+            _ => SearchRes::UnSAT(self.stats()),
+        }
+    }
+
+    // Same as 'search', but the clauses learnt remain for the following searches.
+    pub fn search_incremental(&mut self, ss: &SearchSettings, budget: &Budget, assumptions: &[Lit]) -> IncrementalSearchRes {
+        match self.search_internal(ss, budget, assumptions) {
+            LoopRes::SAT => {
+                let model = extract_model(&self.bt.assigns);
+                self.cancel_until(GROUND_LEVEL);
+                IncrementalSearchRes::Sat(model)
+            }
+
+            LoopRes::AssumpsConfl(conflict) => {
+                self.cancel_until(GROUND_LEVEL);
+                IncrementalSearchRes::Failed(conflict.iter().map(|(lit, _)| !lit).collect())
+            }
+
+            LoopRes::Interrupted(c) => IncrementalSearchRes::Interrupted(c),
+            _ => IncrementalSearchRes::UnSat,
+        }
+    }
+
+    fn search_internal(&mut self, ss: &SearchSettings, budget: &Budget, assumptions: &[Lit]) -> LoopRes {
         info!("============================[ Search Statistics ]==============================");
         info!("| Conflicts |          ORIGINAL         |          LEARNT          | Progress |");
         info!("|           |    Vars  Clauses Literals |    Limit  Clauses Lit/Cl |          |");
         info!("===============================================================================");
 
-        let res = self.search_internal(ss, budget, assumptions);
+        let res = self.search_restarts(ss, budget, assumptions);
 
         info!("===============================================================================");
         res
     }
 
-    fn search_internal(mut self, ss: &SearchSettings, budget: &Budget, assumptions: &[Lit]) -> SearchRes {
-        self.ctx.stats.solves += 1;
-        let mut learnt = LearningGuard::new(ss.learn);
-        learnt.reset(self.ctx.db.stats.num_clauses);
+    // Searches until the result is known or the budget is exhausted (never returns 'LoopRes::Restart').
+    fn search_restarts(&mut self, ss: &SearchSettings, budget: &Budget, assumptions: &[Lit]) -> LoopRes {
+        let mut schedule =
+            match self.schedule.take() {
+                Some(schedule) if schedule.assumptions == assumptions => schedule,
+                _ => {
+                    self.ctx.stats.solves += 1;
+                    self.ctx.heur.set_stable(false, &self.bt.assigns);
+                    Schedule::new(ss, assumptions, self.ctx.db.stats.num_clauses, self.ctx.stats.conflicts, self.bt.propagations())
+                }
+            };
+        self.ctx.heur.track_best = ss.rephase.enabled;
+
+        let res = self.search_schedule(ss, budget, &mut schedule, assumptions);
+        if let LoopRes::Interrupted(_) = res {
+            self.schedule = Some(schedule);
+        }
+        res
+    }
+
+    fn search_schedule(&mut self, ss: &SearchSettings, budget: &Budget, schedule: &mut Schedule, assumptions: &[Lit]) -> LoopRes {
+        let Schedule { ref mut learnt, ref mut vivify, ref mut restarts, ref mut mode, ref mut rephase, ref mut walk, .. } = *schedule;
+
+        if ss.local_search.standalone {
+            let unsat = self.walk(ss.local_search.algorithm, ss.local_search.max_flips, &mut walk.rand, true, budget);
+
+            if unsat > 0 {
+                return LoopRes::Interrupted(progress_estimate(&self.bt.assigns));
+            }
+
+            // The model is read by following the saved phases, which has no conflicts unless the
+            // assumptions contradict it. A conflict gives up rather than start a search:
+            let mut replay = RestartGuard::new(RestartStrategy { policy: RestartPolicy::Static, luby_restart: false, restart_first: 1.0, ..ss.restart });
+            return match self.search_loop(&mut replay, mode, budget, learnt, assumptions) {
+                LoopRes::Restart => LoopRes::Interrupted(progress_estimate(&self.bt.assigns)),
+                res => res,
+            };
+        }
 
-        let mut curr_restarts = 0;
         loop {
-            let conflicts_to_go = ss.restart.conflicts_to_go(curr_restarts);
-            match self.search_loop(conflicts_to_go, budget, &mut learnt, assumptions) {
+            match self.search_loop(&mut restarts[mode.stable as usize], mode, budget, learnt, assumptions) {
                 LoopRes::Restart => {
-                    curr_restarts += 1;
-                }
+                    if let Some(mut observer) = self.ctx.observer.take() {
+                        observer.restarted(&self.ctx.progress(&self.bt));
+                        self.ctx.observer = Some(observer);
+                    }
 
-                LoopRes::SAT => {
-                    let stats = self.stats();
-                    return SearchRes::SAT(self.bt.assigns, stats);
-                }
+                    restarts[mode.stable as usize].restart();
+                    self.ctx.heur.reset_target();
 
-                LoopRes::UnSAT => {
-                    return SearchRes::UnSAT(self.stats());
-                }
+                    if mode.should_switch(self.ctx.stats.conflicts, self.bt.propagations()) {
+                        mode.switch(self.bt.propagations());
+                        self.ctx.heur.set_stable(mode.stable, &self.bt.assigns);
+                        self.ctx.stats.mode_switches += 1;
+                    }
+
+                    match rephase.start(self.ctx.stats.conflicts) {
+                        Some(RephaseStep::Reset(kind)) => {
+                            self.ctx.heur.rephase(kind);
+                            self.ctx.stats.rephases += 1;
+                        }
+
+                        Some(RephaseStep::Walk) => {
+                            let flips = walk.start(self.bt.propagations());
+                            self.walk(ss.local_search.algorithm, flips, &mut walk.rand, false, budget);
+                            self.ctx.stats.rephases += 1;
+                        }
+
+                        None => {}
+                    }
+
+                    if !self.import_clauses() {
+                        return LoopRes::UnSAT;
+                    }
 
-                LoopRes::AssumpsConfl(_) => {
-                    // TODO: implement properly
-                    self.cancel_until(GROUND_LEVEL);
-                    return SearchRes::UnSAT(self.stats());
+                    if let Some(prop_limit) = vivify.start(self.ctx.stats.conflicts, self.bt.propagations()) {
+                        if !self.vivify(&ss.vivify, prop_limit) {
+                            return LoopRes::UnSAT;
+                        }
+                    }
                 }
 
-                LoopRes::Interrupted(c) => {
-                    return SearchRes::Interrupted(c, self);
+                res => {
+                    return res;
                 }
             }
         }
     }
 
     // Description:
-    //   Search for a model the specified number of conflicts.
-    //   NOTE! Use negative value for 'nof_conflicts' indicate infinity.
+    //   Search for a model until 'restart' decides to restart.
     //
     // Output:
     //   'l_True' if a partial assigment that is consistent with respect to the clauseset is found. If
@@ -451,16 +1061,17 @@ impl Searcher {
     //   if the clause set is unsatisfiable. 'l_Undef' if the bound on number of conflicts is reached.
     fn search_loop(
         &mut self,
-        nof_conflicts: u64,
+        restart: &mut RestartGuard,
+        mode: &ModeGuard,
         budget: &Budget,
         learnt: &mut LearningGuard,
         assumptions: &[Lit],
     ) -> LoopRes {
         self.ctx.stats.starts += 1;
 
-        let confl_limit = self.ctx.stats.conflicts + nof_conflicts;
+        restart.start(self.ctx.stats.conflicts);
         loop {
-            if !self.propagate_learn_backtrack(learnt) {
+            if !self.propagate_learn_backtrack(learnt, restart) {
                 return LoopRes::UnSAT;
             }
 
@@ -470,7 +1081,8 @@ impl Searcher {
                 return LoopRes::Interrupted(progress_estimate);
             }
 
-            if self.ctx.stats.conflicts >= confl_limit {
+            if restart.should_restart(self.ctx.stats.conflicts) || mode.should_switch(self.ctx.stats.conflicts, self.bt.propagations()) {
+                self.ctx.update_phases(&self.bt.assigns, false);
                 self.cancel_until(GROUND_LEVEL);
                 return LoopRes::Restart;
             }
@@ -480,19 +1092,34 @@ impl Searcher {
 
             if (self.ctx.db.number_of_learnts() as f64) >= learnt.border() + (self.bt.assigns.number_of_assigns() as f64) {
                 // Reduce the set of learnt clauses:
+                let before = self.ctx.db.number_of_learnts();
                 {
                     let watches = &mut self.bt.watches;
                     self.ctx.db.reduce(&mut self.bt.ca, &self.bt.assigns, move |c| {
                         watches.unwatch_clause_lazy(c);
                     });
                 }
+                self.ctx.stats.reductions += 1;
+                if let Some(ref mut observer) = self.ctx.observer {
+                    observer.reduced(before, self.ctx.db.number_of_learnts());
+                }
 
                 self.try_garbage_collect();
             }
 
-            match self.ctx.decide(&mut self.bt.assigns, &self.bt.ca, assumptions) {
-                Err(confl) => { return LoopRes::AssumpsConfl(confl) }
-                Ok(None) => { return LoopRes::SAT } // Model found:
+            match self.ctx.decide(&mut self.bt.assigns, &mut self.bt.ca, assumptions) {
+                Err(confl) => {
+                    self.ctx.adopt_reasons(&mut self.bt);
+                    return LoopRes::AssumpsConfl(confl);
+                }
+
+                // Model found, unless the external propagator or the model refinement rejects it:
+                Ok(None) => match self.check_model() {
+                    ModelCheck::Accepted => { return LoopRes::SAT }
+                    ModelCheck::Refined => {}
+                    ModelCheck::UnSAT => { return LoopRes::UnSAT }
+                },
+
                 Ok(Some(next)) => {
                     self.bt.push_decision(next);
                 }
@@ -500,20 +1127,72 @@ impl Searcher {
         }
     }
 
-    fn propagate_learn_backtrack(&mut self, learnt: &mut LearningGuard) -> bool {
-        while let Some(confl) = self.bt.propagate() {
-            match self.ctx.handle_conflict(learnt, &mut self.bt, confl) {
+    fn propagate_learn_backtrack(&mut self, learnt: &mut LearningGuard, restart: &mut RestartGuard) -> bool {
+        loop {
+            let confl =
+                match self.propagate() {
+                    Ok(Some(confl)) => confl,
+                    Ok(None) => { return true; }
+                    Err(()) => { return false; }
+                };
+
+            if self.settings.chrono.is_some() || self.ctx.external.is_some() {
+                // The conflict may be at a lower level than the current one:
+                let (level, second) = self.watch_highest_levels(confl);
+                if level.is_ground() {
+                    return false;
+                }
+
+                if second < level {
+                    // The clause is asserting at the second level, no need to analyze it:
+                    self.cancel_until(second);
+                    let lit = self.bt.ca.view(confl).prefix[0];
+                    self.bt.assigns.assign_lit(lit, Some(confl));
+                    continue;
+                }
+
+                self.cancel_until(level);
+            }
+
+            match self.ctx.handle_conflict(learnt, restart, &mut self.bt, confl, self.settings.chrono) {
                 None => { return false; }
-                Some((level, lit, reason)) => {
-                    self.bt.assigns.backtrack_to(level);
-                    self.bt.assigns.assign_lit(lit, reason);
+                Some((target, lit, level, reason)) => {
+                    self.bt.assigns.backtrack_to(target);
+                    self.bt.assigns.assign_lit_at(lit, reason, level);
                     for &cr in reason.iter() {
                         self.bt.attach(cr);
                     }
                 }
             }
         }
-        true
+    }
+
+    // Moves two literals of the falsified clause with the highest levels to the watched positions,
+    // so that the watches stay valid after backtracking. Returns the levels of those literals.
+    fn watch_highest_levels(&mut self, cr: ClauseRef) -> (DecisionLevel, DecisionLevel) {
+        let (i0, i1) = {
+            let assigns = &self.bt.assigns;
+            let lits = self.bt.ca.literals(cr);
+            let level = |i: usize| assigns.level(lits[i].var());
+
+            let i0 = (1..lits.len()).fold(0, |best, i| if level(i) > level(best) { i } else { best });
+            let i1 = (0..lits.len()).filter(|&i| i != i0)
+                .fold(if i0 == 0 { 1 } else { 0 }, |best, i| if level(i) > level(best) { i } else { best });
+            (i0, i1)
+        };
+
+        if i0 > 1 || i1 > 1 {
+            self.bt.force_detach(cr);
+            let lits = self.bt.ca.edit(cr).lits_mut();
+            lits.swap(0, i0);
+            lits.swap(1, if i1 == 0 { i0 } else { i1 });
+            self.bt.attach(cr);
+        } else if i0 == 1 {
+            self.bt.ca.edit(cr).prefix.swap(0, 1);
+        }
+
+        let c = self.bt.ca.view(cr);
+        (self.bt.assigns.level(c.prefix[0].var()), self.bt.assigns.level(c.prefix[1].var()))
     }
 
     // Description:
@@ -526,12 +1205,16 @@ impl Searcher {
             return;
         }
 
+        let before = self.ctx.db.stats.num_clauses + self.ctx.db.stats.num_learnts;
         {
             let watches = &mut self.bt.watches;
             self.ctx.db.remove_satisfied(&mut self.bt.ca, &self.bt.assigns, move |c| {
                 watches.unwatch_clause_lazy(c);
             });
         }
+        if let Some(ref mut observer) = self.ctx.observer {
+            observer.simplified(before - self.ctx.db.stats.num_clauses - self.ctx.db.stats.num_learnts);
+        }
 
         //        // TODO: why if?
         //        if self.db.settings.remove_satisfied {
@@ -567,6 +1250,162 @@ impl Searcher {
         ); // (shouldn't depend on stats really, but it will do for now)
     }
 
+    // Description:
+    //   Shorten good learnt clauses (and problem clauses if enabled) by propagating the negation of
+    //   their literals, until 'prop_limit' propagations are made. Every clause is vivified at most
+    //   once. Returns false if the formula became unsatisfiable.
+    fn vivify(&mut self, vs: &VivifyStrategy, prop_limit: u64) -> bool {
+        assert!(self.bt.is_ground_level());
+
+        let mut candidates = Vec::new();
+        if vs.learnts {
+            let ca = &self.bt.ca;
+            let mut learnts: Vec<ClauseRef> = self.ctx.db.learnts().iter()
+                .cloned()
+                .filter(|&cr| {
+                    let c = ca.view(cr);
+                    !c.is_deleted() && !c.is_vivified() && c.len() > 2 && c.header.lbd() <= vs.max_lbd
+                })
+                .collect();
+
+            // Best clauses first:
+            learnts.sort_by(|&rx, &ry| {
+                let x = ca.view(rx).header;
+                let y = ca.view(ry).header;
+                x.lbd().cmp(&y.lbd()).then(y.activity().partial_cmp(&x.activity()).unwrap())
+            });
+            candidates.extend(learnts);
+        }
+
+        // Problem clauses are referenced from the occurrence lists while the simplificator is on:
+        if vs.clauses && !self.bt.ca.extra_clause_field {
+            let ca = &self.bt.ca;
+            candidates.extend(self.ctx.db.clauses().iter().cloned().filter(|&cr| {
+                let c = ca.view(cr);
+                !c.is_deleted() && !c.is_vivified() && c.len() > 2
+            }));
+        }
+
+        let before = (self.ctx.stats.vivified_clauses, self.ctx.stats.vivified_literals);
+        for cr in candidates {
+            if self.bt.propagations() >= prop_limit {
+                break;
+            }
+
+            if self.bt.ca.is_deleted(cr) || satisfied_with_assignment(self.bt.ca.literals(cr), &self.bt.assigns) {
+                continue;
+            }
+
+            self.bt.force_detach(cr);
+            match vivify_clause(&mut self.bt, cr) {
+                None => {
+                    self.bt.ca.edit(cr).set_vivified();
+                    self.bt.attach(cr);
+                }
+
+                Some(lits) => {
+                    self.ctx.stats.vivified_clauses += 1;
+                    self.ctx.stats.vivified_literals += (self.bt.ca.view(cr).len() - lits.len()) as u64;
+
+                    match &lits[..] {
+                        [] => { return false; }
+
+                        [unit] => {
+                            self.ctx.db.remove_clause(&mut self.bt.ca, cr);
+                            self.bt.assigns.assign_lit(*unit, None);
+                            if self.bt.propagate().is_some() {
+                                return false;
+                            }
+                        }
+
+                        lits => {
+                            self.ctx.db.edit_clause(&mut self.bt.ca, cr, |c| {
+                                let shrink = c.len() - lits.len();
+                                c.lits_mut()[..lits.len()].copy_from_slice(lits);
+                                c.shrink_by(shrink);
+                                c.set_vivified();
+                                if let ClauseHeader::Clause { abstraction: Some(ref mut abstraction) } = c.header {
+                                    *abstraction = calc_abstraction(lits);
+                                }
+                            });
+                            self.bt.attach(cr);
+                        }
+                    }
+                }
+            }
+        }
+
+        trace!(
+            "Vivification: {} clauses shortened by {} literals",
+            self.ctx.stats.vivified_clauses - before.0,
+            self.ctx.stats.vivified_literals - before.1
+        );
+
+        self.try_garbage_collect();
+        true
+    }
+
+    // Adds the clauses supplied by the clause exchange as learnt clauses. Clauses over variables that
+    // are not decision variables here (e.g. eliminated ones) are skipped. Returns false if the formula
+    // became unsatisfiable.
+    fn import_clauses(&mut self) -> bool {
+        let clauses =
+            match self.ctx.exchange {
+                Some(ref mut exchange) => exchange.import(),
+                None => return true,
+            };
+
+        assert!(self.bt.is_ground_level());
+        for clause in clauses {
+            let assigns = &self.bt.assigns;
+            let heur = &self.ctx.heur;
+            if clause.iter().any(|&lit| !heur.is_decision(lit.var()) || assigns.is_assigned_pos(lit)) {
+                continue;
+            }
+
+            let lits: Vec<Lit> = clause.into_iter().filter(|&lit| !assigns.is_assigned_neg(lit)).collect();
+            self.ctx.stats.imported_clauses += 1;
+            match &lits[..] {
+                [] => { return false; }
+
+                [unit] => {
+                    self.bt.assigns.assign_lit(*unit, None);
+                    if self.bt.propagate().is_some() {
+                        return false;
+                    }
+                }
+
+                lits => {
+                    let cr = self.ctx.db.learn_clause(&mut self.bt.ca, lits, lits.len() as u32);
+                    self.bt.attach(cr);
+                }
+            }
+        }
+
+        true
+    }
+
+    // Runs local search from the saved phases (or a random assignment) and replaces the saved phases
+    // with the best assignment found. Returns the number of clauses falsified by it.
+    fn walk(&mut self, algorithm: LocalSearchAlgorithm, max_flips: u64, rand: &mut Random, random_init: bool, budget: &Budget) -> usize {
+        let mut ls = LocalSearch::new(algorithm, &self.bt.ca, self.ctx.db.clauses(), &self.bt.assigns);
+        if random_init {
+            ls.init(|_| rand.chance(0.5));
+        } else {
+            let heur = &self.ctx.heur;
+            ls.init(|v| heur.phase(v));
+        }
+
+        let unsat = ls.run(max_flips, rand, budget);
+        for lit in ls.best() {
+            self.ctx.heur.set_phase(lit);
+        }
+
+        self.ctx.stats.walk_flips += ls.flips;
+        trace!("Local search: {} flips, {} falsified clauses", ls.flips, unsat);
+        unsat
+    }
+
     // Revert to the state at given level (keeping all assignment at 'level' but not beyond).
     fn cancel_until(&mut self, target_level: DecisionLevel) {
         self.ctx.cancel_until(&self.bt.assigns, target_level);
@@ -576,16 +1415,26 @@ impl Searcher {
 
     fn try_garbage_collect(&mut self) {
         if self.bt.ca.check_garbage(self.settings.garbage_frac) {
+            let before = self.bt.ca.allocated_bytes();
             self.gc();
+            self.garbage_collected(before);
         }
     }
 
     fn gc(&mut self) -> ClauseGC {
+        self.ctx.stats.garbage_collections += 1;
         let mut gc = self.bt.gc();
         self.ctx.db.gc(&mut gc);
         gc
     }
 
+    // Reports a garbage collection that started with 'before' bytes, once the relocation is done.
+    fn garbage_collected(&mut self, before: usize) {
+        if let Some(ref mut observer) = self.ctx.observer {
+            observer.garbage_collected(before, self.bt.ca.allocated_bytes());
+        }
+    }
+
 
     pub fn stats(&self) -> sat::Stats {
         sat::Stats {
@@ -597,6 +1446,25 @@ impl Searcher {
             propagations: self.bt.propagations(),
             tot_literals: self.ctx.analyze.tot_literals,
             del_literals: self.ctx.analyze.max_literals - self.ctx.analyze.tot_literals,
+            learnts: self.ctx.stats.learnts,
+            learnt_lbds: self.ctx.stats.learnt_lbds,
+            conflict_trails: self.ctx.stats.conflict_trails,
+            reductions: self.ctx.stats.reductions,
+            garbage_collections: self.ctx.stats.garbage_collections,
+            eliminated_vars: self.ctx.stats.eliminated_vars,
+            asymm_lits: self.ctx.stats.asymm_lits,
+            equiv_vars: self.ctx.stats.equiv_vars,
+            blocked_clauses: self.ctx.stats.blocked_clauses,
+            added_vars: self.ctx.stats.added_vars,
+            vivified_clauses: self.ctx.stats.vivified_clauses,
+            vivified_literals: self.ctx.stats.vivified_literals,
+            gates: self.ctx.stats.gates,
+            chrono_backtracks: self.ctx.stats.chrono_backtracks,
+            blocked_restarts: self.ctx.stats.blocked_restarts,
+            mode_switches: self.ctx.stats.mode_switches,
+            rephases: self.ctx.stats.rephases,
+            walk_flips: self.ctx.stats.walk_flips,
+            ..Default::default()
         }
     }
 }
@@ -2,6 +2,7 @@ use crate::sat::formula::{assignment::*, clause::*, Lit, Var};
 use super::watches::Watches;
 
 
+#[derive(Clone)]
 pub struct BacktrackableFormula {
     pub ca: ClauseAllocator,
     pub assigns: Assignment, // The current assignments.
@@ -0,0 +1,263 @@
+use std::io;
+use crate::sat::formula::{Idx, Lit, Var, VarMap};
+use super::{Schedule, SearchSettings, Searcher};
+use super::random::Random;
+
+
+const MAGIC: &[u8; 8] = b"MSRSCKPT";
+const VERSION: u64 = 2;
+
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid checkpoint: {}", msg))
+}
+
+
+// Binary checkpoint format: a header with the version, followed by little-endian 64-bit integers,
+// floats (by their bits) and literal lists written by the parts of the solver in a fixed order.
+pub struct CheckpointWriter<W> {
+    writer: W,
+}
+
+impl<W: io::Write> CheckpointWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        Ok(CheckpointWriter { writer })
+    }
+
+    pub fn u64(&mut self, x: u64) -> io::Result<()> {
+        self.writer.write_all(&x.to_le_bytes())
+    }
+
+    pub fn usize(&mut self, x: usize) -> io::Result<()> {
+        self.u64(x as u64)
+    }
+
+    pub fn f64(&mut self, x: f64) -> io::Result<()> {
+        self.u64(x.to_bits())
+    }
+
+    pub fn option_bool(&mut self, x: Option<bool>) -> io::Result<()> {
+        self.u64(match x { None => 0, Some(false) => 1, Some(true) => 2 })
+    }
+
+    pub fn lits(&mut self, lits: &[Lit]) -> io::Result<()> {
+        self.usize(lits.len())?;
+        for lit in lits.iter() {
+            self.usize(lit.idx())?;
+        }
+        Ok(())
+    }
+
+    // E.g. the DIMACS numbering of the variables.
+    pub fn var_map(&mut self, map: &VarMap<i32>) -> io::Result<()> {
+        self.usize(map.iter().count())?;
+        for (v, &x) in map.iter() {
+            self.usize(v.idx())?;
+            self.u64(x as u32 as u64)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+
+pub struct CheckpointReader<R> {
+    reader: R,
+}
+
+impl<R: io::Read> CheckpointReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a checkpoint file"));
+        }
+
+        let mut r = CheckpointReader { reader };
+        let version = r.u64()?;
+        if version != VERSION {
+            return Err(invalid(&format!("unsupported version {}", version)));
+        }
+        Ok(r)
+    }
+
+    pub fn u64(&mut self) -> io::Result<u64> {
+        let mut buf = [0; 8];
+        self.reader.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    pub fn usize(&mut self) -> io::Result<usize> {
+        self.u64().map(|x| x as usize)
+    }
+
+    pub fn f64(&mut self) -> io::Result<f64> {
+        self.u64().map(f64::from_bits)
+    }
+
+    pub fn option_bool(&mut self) -> io::Result<Option<bool>> {
+        match self.u64()? {
+            0 => Ok(None),
+            1 => Ok(Some(false)),
+            2 => Ok(Some(true)),
+            _ => Err(invalid("bad boolean")),
+        }
+    }
+
+    // Literals over the first 'vars' variables.
+    pub fn lits(&mut self, vars: usize) -> io::Result<Vec<Lit>> {
+        let len = self.usize()?;
+        let mut lits = Vec::new();
+        for _ in 0..len {
+            let lit = Lit::unidx(self.usize()?);
+            if lit.var().idx() >= vars {
+                return Err(invalid("unknown variable"));
+            }
+            lits.push(lit);
+        }
+        Ok(lits)
+    }
+
+    pub fn var_map(&mut self) -> io::Result<VarMap<i32>> {
+        let mut map = VarMap::new();
+        for _ in 0..self.usize()? {
+            let v = Var::unidx(self.usize()?);
+            map.insert(&v, self.u64()? as u32 as i32);
+        }
+        Ok(map)
+    }
+}
+
+
+impl Searcher {
+    // Writes the state of the search at the ground level: the variables with their heuristic state,
+    // the ground assignment, problem and learnt clauses, the statistics and the schedule of the
+    // interrupted search. Watches and the order of the decision heuristic are rebuilt on resume.
+    pub fn write_checkpoint<W: io::Write>(&self, w: &mut CheckpointWriter<W>) -> io::Result<()> {
+        assert!(self.bt.is_ground_level());
+        w.usize(self.bt.assigns.number_of_vars())?;
+        self.ctx.heur.write_checkpoint(w)?;
+        w.lits(self.bt.assigns.trail())?;
+        self.ctx.db.write_checkpoint(&self.bt.ca, w)?;
+
+        let s = &self.ctx.stats;
+        for &x in [
+            s.solves, s.starts, s.decisions, s.conflicts, s.chrono_backtracks, s.blocked_restarts,
+            s.mode_switches, s.rephases, s.walk_flips, s.imported_clauses, s.vivified_clauses,
+            s.vivified_literals, s.learnts, s.learnt_lbds, s.conflict_trails, s.reductions,
+            s.garbage_collections, s.eliminated_vars, s.asymm_lits, s.equiv_vars, s.blocked_clauses,
+            s.added_vars, s.gates,
+            self.ctx.analyze.max_literals, self.ctx.analyze.tot_literals, self.bt.propagations(),
+        ].iter() {
+            w.u64(x)?;
+        }
+
+        w.u64(self.schedule.is_some() as u64)?;
+        if let Some(ref schedule) = self.schedule {
+            schedule.write_checkpoint(w)?;
+        }
+        Ok(())
+    }
+
+    // Restores a checkpoint into a searcher without variables. The schedule continues with the
+    // settings 'ss'. Returns false if the formula is unsatisfiable.
+    pub fn read_checkpoint<R: io::Read>(&mut self, r: &mut CheckpointReader<R>, ss: &SearchSettings) -> io::Result<bool> {
+        assert_eq!(self.bt.assigns.number_of_vars(), 0);
+        let vars = r.usize()?;
+        for _ in 0..vars {
+            self.new_var(None, false);
+        }
+        self.ctx.heur.read_checkpoint(r, vars)?;
+
+        for lit in r.lits(vars)? {
+            if self.bt.assigns.is_undef(lit.var()) {
+                self.bt.assigns.assign_lit(lit, None);
+            }
+        }
+
+        for cr in self.ctx.db.read_checkpoint(&mut self.bt.ca, r, vars)? {
+            self.bt.attach(cr);
+        }
+
+        {
+            let s = &mut self.ctx.stats;
+            for x in [
+                &mut s.solves, &mut s.starts, &mut s.decisions, &mut s.conflicts, &mut s.chrono_backtracks,
+                &mut s.blocked_restarts, &mut s.mode_switches, &mut s.rephases, &mut s.walk_flips,
+                &mut s.imported_clauses, &mut s.vivified_clauses, &mut s.vivified_literals, &mut s.learnts,
+                &mut s.learnt_lbds, &mut s.conflict_trails, &mut s.reductions, &mut s.garbage_collections,
+                &mut s.eliminated_vars, &mut s.asymm_lits, &mut s.equiv_vars, &mut s.blocked_clauses,
+                &mut s.added_vars, &mut s.gates,
+                &mut self.ctx.analyze.max_literals, &mut self.ctx.analyze.tot_literals,
+            ].iter_mut() {
+                **x = r.u64()?;
+            }
+        }
+        let propagations = r.u64()?;
+
+        self.schedule =
+            if r.u64()? != 0 {
+                let mut schedule = Schedule::new(ss, &[], 0, 0, 0);
+                schedule.read_checkpoint(r, vars)?;
+                Some(schedule)
+            } else {
+                None
+            };
+
+        // The ground assignment is propagated again to restore the watches:
+        let ok = self.bt.propagate().is_none();
+        self.bt.watches.propagations = propagations;
+        self.ctx.heur.rebuild_order_heap(&self.bt.assigns);
+        Ok(ok)
+    }
+}
+
+
+impl Schedule {
+    fn write_checkpoint<W: io::Write>(&self, w: &mut CheckpointWriter<W>) -> io::Result<()> {
+        w.lits(&self.assumptions)?;
+        w.f64(self.learnt.max_learnts)?;
+        w.f64(self.learnt.size_adjust_confl)?;
+        w.u64(self.learnt.size_adjust_cnt as i64 as u64)?;
+        w.u64(self.vivify.next_conflicts)?;
+        w.u64(self.vivify.last_propagations)?;
+        for restart in self.restarts.iter() {
+            restart.write_checkpoint(w)?;
+        }
+        w.u64(self.mode.stable as u64)?;
+        w.u64(self.mode.conflicts_limit.map_or(0, |limit| limit + 1))?;
+        w.u64(self.mode.phase_propagations)?;
+        w.u64(self.mode.start_propagations)?;
+        w.u64(self.rephase.rephases)?;
+        w.u64(self.rephase.next_conflicts)?;
+        w.f64(self.walk.rand.seed())?;
+        w.u64(self.walk.last_propagations)
+    }
+
+    fn read_checkpoint<R: io::Read>(&mut self, r: &mut CheckpointReader<R>, vars: usize) -> io::Result<()> {
+        self.assumptions = r.lits(vars)?;
+        self.learnt.max_learnts = r.f64()?;
+        self.learnt.size_adjust_confl = r.f64()?;
+        self.learnt.size_adjust_cnt = r.u64()? as i64 as i32;
+        self.vivify.next_conflicts = r.u64()?;
+        self.vivify.last_propagations = r.u64()?;
+        for restart in self.restarts.iter_mut() {
+            restart.read_checkpoint(r)?;
+        }
+        self.mode.stable = r.u64()? != 0;
+        self.mode.conflicts_limit = r.u64()?.checked_sub(1);
+        self.mode.phase_propagations = r.u64()?;
+        self.mode.start_propagations = r.u64()?;
+        self.rephase.rephases = r.u64()?;
+        self.rephase.next_conflicts = r.u64()?;
+        self.walk.rand = Random::new(r.f64()?);
+        self.walk.last_propagations = r.u64()?;
+        Ok(())
+    }
+}
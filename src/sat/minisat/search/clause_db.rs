@@ -1,7 +1,10 @@
 use std::cmp::Ordering;
+use std::io;
 use crate::sat::formula::{assignment::Assignment, clause::*, util::*, Lit};
+use super::checkpoint::{CheckpointReader, CheckpointWriter};
 
 
+#[derive(Clone, Copy, Debug)]
 pub struct ClauseDBSettings {
     pub remove_satisfied: bool, // Indicates whether possibly inefficient linear scan for satisfied clauses should be performed in 'simplify'.
     pub clause_decay: f64,
@@ -28,7 +31,7 @@ pub struct Stats {
 impl Stats {
     fn add(&mut self, clause: &Clause) {
         match clause.header {
-            ClauseHeader::Learnt { activity: _ } => {
+            ClauseHeader::Learnt { .. } => {
                 self.num_learnts += 1;
                 self.learnts_literals += clause.len() as u64;
             }
@@ -42,7 +45,7 @@ impl Stats {
 
     fn del(&mut self, clause: &Clause) {
         match clause.header {
-            ClauseHeader::Learnt { activity: _ } => {
+            ClauseHeader::Learnt { .. } => {
                 self.num_learnts -= 1;
                 self.learnts_literals -= clause.len() as u64;
             }
@@ -56,6 +59,7 @@ impl Stats {
 }
 
 
+#[derive(Clone)]
 pub struct ClauseDB {
     pub settings: ClauseDBSettings,
     cla_inc: f64,            // Amount to bump next clause with.
@@ -90,13 +94,18 @@ impl ClauseDB {
         cr
     }
 
-    pub fn learn_clause<'c>(&mut self, ca: &mut ClauseAllocator, literals: &[Lit]) -> ClauseRef {
-        let header = ClauseHeader::Learnt { activity: 0.0 };
-        let (c, cr) = ca.alloc(literals, header);
-        self.stats.add(c);
+    pub fn learn_clause<'c>(&mut self, ca: &mut ClauseAllocator, literals: &[Lit], lbd: u32) -> ClauseRef {
+        let header = ClauseHeader::learnt(0.0, lbd);
+        let (_, cr) = ca.alloc(literals, header);
+        self.adopt_learnt(ca, cr);
+        cr
+    }
+
+    // Takes a learnt clause allocated elsewhere (e.g. during the conflict analysis).
+    pub fn adopt_learnt(&mut self, ca: &mut ClauseAllocator, cr: ClauseRef) {
+        self.stats.add(ca.view(cr));
         self.learnts.push(cr);
         self.bump_activity(ca, cr);
-        cr
     }
 
     pub fn remove_clause(&mut self, ca: &mut ClauseAllocator, cr: ClauseRef) {
@@ -119,7 +128,7 @@ impl ClauseDB {
     pub fn bump_activity(&mut self, ca: &mut ClauseAllocator, cr: ClauseRef) {
         let new = {
             let c = ca.edit(cr);
-            if let ClauseHeader::Learnt { ref mut activity } = c.header {
+            if let ClauseHeader::Learnt { ref mut activity, .. } = c.header {
                 let new = *activity as f64 + self.cla_inc;
                 *activity = new as f32;
                 new
@@ -132,7 +141,7 @@ impl ClauseDB {
             self.cla_inc *= 1e-20;
             for &cri in self.learnts.iter() {
                 let c = ca.edit(cri);
-                if let ClauseHeader::Learnt { ref mut activity } = c.header {
+                if let ClauseHeader::Learnt { ref mut activity, .. } = c.header {
                     let scaled = (*activity as f64) * 1e-20;
                     *activity = scaled as f32;
                 } else {
@@ -150,6 +159,62 @@ impl ClauseDB {
         self.learnts.len()
     }
 
+    pub fn clauses(&self) -> &[ClauseRef] {
+        &self.clauses
+    }
+
+    pub fn learnts(&self) -> &[ClauseRef] {
+        &self.learnts
+    }
+
+    pub fn write_checkpoint<W: io::Write>(&self, ca: &ClauseAllocator, w: &mut CheckpointWriter<W>) -> io::Result<()> {
+        w.f64(self.cla_inc)?;
+        for list in [&self.clauses, &self.learnts].iter() {
+            let live: Vec<ClauseRef> = list.iter().cloned().filter(|&cr| !ca.is_deleted(cr)).collect();
+            w.usize(live.len())?;
+            for cr in live {
+                let c = ca.view(cr);
+                w.lits(c.lits())?;
+                if let ClauseHeader::Learnt { activity, lbd } = c.header {
+                    w.f64(activity as f64)?;
+                    w.u64(lbd as u64)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the restored clauses, they have to be attached.
+    pub fn read_checkpoint<R: io::Read>(&mut self, ca: &mut ClauseAllocator, r: &mut CheckpointReader<R>, vars: usize) -> io::Result<Vec<ClauseRef>> {
+        let cla_inc = r.f64()?;
+        let mut restored = Vec::new();
+        for _ in 0..r.usize()? {
+            let lits = r.lits(vars)?;
+            if lits.len() < 2 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid checkpoint: short clause"));
+            }
+            restored.push(self.add_clause(ca, &lits));
+        }
+
+        for _ in 0..r.usize()? {
+            let lits = r.lits(vars)?;
+            let saved = r.f64()? as f32;
+            let lbd = r.u64()? as u32;
+            if lits.len() < 2 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid checkpoint: short clause"));
+            }
+
+            let cr = self.learn_clause(ca, &lits, lbd);
+            if let ClauseHeader::Learnt { ref mut activity, .. } = ca.edit(cr).header {
+                *activity = saved;
+            }
+            restored.push(cr);
+        }
+
+        self.cla_inc = cla_inc;
+        Ok(restored)
+    }
+
     // Description:
     //   Remove half of the learnt clauses, minus the clauses locked by the current assignment. Locked
     //   clauses are clauses that are reason to some assignment. Binary clauses are never removed.
@@ -1,7 +1,7 @@
 use crate::sat::formula::{assignment::*, clause::*, Lit, LitMap, Var, VarMap};
 
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum CCMinMode {
     None,
     Basic,
@@ -25,6 +25,15 @@ enum Seen {
 }
 
 
+// Reason of an implied literal without a reason clause in the assignment, i.e. implied by an
+// external propagator and explained on demand.
+pub enum Reason {
+    Decision,
+    Fact, // Implied regardless of the assignment.
+    Clause(ClauseRef),
+}
+
+
 pub enum Conflict {
     Ground,
     Unit(DecisionLevel, Lit),
@@ -32,6 +41,7 @@ pub enum Conflict {
 }
 
 
+#[derive(Clone)]
 pub struct AnalyzeContext {
     ccmin_mode: CCMinMode, // Controls conflict clause minimization
     seen: VarMap<Seen>,
@@ -61,23 +71,26 @@ impl AnalyzeContext {
     //   Pre-conditions:
     //     * 'out_learnt' is assumed to be cleared.
     //     * Current decision level must be greater than root level.
+    //     * At least two literals of the conflict clause are assigned at the current level.
     //
     //   Post-conditions:
     //     * 'out_learnt[0]' is the asserting literal at level 'out_btlevel'.
     //     * If out_learnt.size() > 1 then 'out_learnt[1]' has the greatest decision level of the
     //       rest of literals. There may be others from the same level though.
     //
-    pub fn analyze<BV, BC>(
+    pub fn analyze<BV, BC, R>(
         &mut self,
         assigns: &Assignment,
         ca: &mut ClauseAllocator,
         confl0: ClauseRef,
         mut bump_var: BV,
         mut bump_cla: BC,
+        mut reason: R,
     ) -> Conflict
     where
         BV: FnMut(Var) -> (),
         BC: FnMut(&mut ClauseAllocator, ClauseRef) -> (),
+        R: FnMut(&mut ClauseAllocator, Lit) -> Reason,
     {
         if assigns.is_ground_level() {
             return Conflict::Ground;
@@ -87,36 +100,39 @@ impl AnalyzeContext {
         let mut out_learnt = Vec::with_capacity(assigns.number_of_assigns());
 
         {
-            let mut confl = confl0;
+            let mut confl = Some(confl0);
             let mut path_c = 0;
 
             let trail = assigns.trail();
             let mut index = trail.len();
             loop {
-                bump_cla(ca, confl);
-
-                let base = if confl == confl0 { 0 } else { 1 };
-                for &q in &ca.view(confl).lits()[base..] {
-                    let v = q.var();
-                    if self.seen[&v] == Seen::Undef {
-                        let level = assigns.vardata(q).level;
-                        if level > GROUND_LEVEL {
-                            self.seen[&v] = Seen::Source;
-                            bump_var(v);
-                            if level >= assigns.current_level() {
-                                path_c += 1;
-                            } else {
-                                out_learnt.push(q);
+                if let Some(cr) = confl {
+                    bump_cla(ca, cr);
+
+                    let base = if cr == confl0 { 0 } else { 1 };
+                    for &q in &ca.view(cr).lits()[base..] {
+                        let v = q.var();
+                        if self.seen[&v] == Seen::Undef {
+                            let level = assigns.vardata(q).level;
+                            if level > GROUND_LEVEL {
+                                self.seen[&v] = Seen::Source;
+                                bump_var(v);
+                                if level >= assigns.current_level() {
+                                    path_c += 1;
+                                } else {
+                                    out_learnt.push(q);
+                                }
                             }
                         }
                     }
                 }
 
-                // Select next clause to look at:
+                // Select next clause to look at (skipping literals assigned out of order at lower levels):
                 let pl = {
                     loop {
                         index -= 1;
-                        if self.seen[ &trail[index].var() ] != Seen::Undef {
+                        let v = trail[index].var();
+                        if self.seen[&v] != Seen::Undef && assigns.level(v) >= assigns.current_level() {
                             break;
                         }
                     }
@@ -131,7 +147,14 @@ impl AnalyzeContext {
                     break;
                 }
 
-                confl = assigns.vardata(!pl).reason.unwrap();
+                confl =
+                    match assigns.vardata(!pl).reason {
+                        Some(cr) => Some(cr),
+                        None => match reason(ca, pl) {
+                            Reason::Clause(cr) => Some(cr),
+                            _ => None,
+                        },
+                    };
             }
         }
 
@@ -252,19 +275,35 @@ impl AnalyzeContext {
     //   Specialized analysis procedure to express the final conflict in terms of assumptions.
     //   Calculates the (possibly empty) set of assumptions that led to the assignment of 'p', and
     //   stores the result in 'out_conflict'.
-    pub fn analyze_final(&mut self, ca: &ClauseAllocator, assigns: &Assignment, p: Lit) -> LitMap<()> {
+    pub fn analyze_final<R>(&mut self, ca: &mut ClauseAllocator, assigns: &Assignment, p: Lit, mut reason: R) -> LitMap<()>
+    where
+        R: FnMut(&mut ClauseAllocator, Lit) -> Reason,
+    {
         let mut out_conflict = LitMap::new();
         out_conflict.insert(&p, ());
 
+        if assigns.level(p.var()) == GROUND_LEVEL {
+            return out_conflict;
+        }
+
+        self.seen[&p.var()] = Seen::Source;
         for &lit in assigns.trail_above(GROUND_LEVEL).iter().rev() {
             if self.seen[&lit.var()] != Seen::Undef {
-                match assigns.vardata(lit).reason {
-                    None => {
-                        assert!(assigns.vardata(lit).level > GROUND_LEVEL);
+                let lit_reason =
+                    match assigns.vardata(!lit).reason {
+                        Some(cr) => Reason::Clause(cr),
+                        None => reason(ca, lit),
+                    };
+
+                match lit_reason {
+                    Reason::Decision => {
+                        assert!(assigns.level(lit.var()) > GROUND_LEVEL);
                         out_conflict.insert(&!lit, ());
                     }
 
-                    Some(cr) => {
+                    Reason::Fact => {}
+
+                    Reason::Clause(cr) => {
                         for &lit in &ca.view(cr).lits()[1..] {
                             if assigns.vardata(lit).level > GROUND_LEVEL {
                                 self.seen[&lit.var()] = Seen::Source;
@@ -272,6 +311,7 @@ impl AnalyzeContext {
                         }
                     }
                 }
+                self.seen[&lit.var()] = Seen::Undef;
             }
         }
 
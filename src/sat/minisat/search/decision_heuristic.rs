@@ -1,8 +1,14 @@
-use crate::sat::formula::{assignment::Assignment, Lit, Var, VarHeap, VarMap};
+use std::{io, mem};
+use crate::sat::formula::{assignment::Assignment, Idx, Lit, Var, VarMap};
+use super::checkpoint::{CheckpointReader, CheckpointWriter};
 use super::random;
 
+mod lrb;
+mod vmtf;
+mod vsids;
 
-#[derive(PartialEq, Eq)]
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum PhaseSaving {
     None,
     Limited,
@@ -10,43 +16,116 @@ pub enum PhaseSaving {
 }
 
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Branching {
+    Vsids, // Variable state independent decaying sum.
+    Vmtf,  // Variable move-to-front.
+    Lrb,   // Learning-rate based branching.
+    Chb,   // Conflict history based branching.
+}
+
+
+// Source of the saved phases on rephasing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rephase {
+    Original, // The initial phase.
+    Inverted, // The opposite of the initial phase.
+    Best,     // The phases of the largest conflict-free assignment since the previous rephasing.
+    Random,
+}
+
+
+// Order in which the decision heuristic considers variables. Implementations get notified about
+// variables returned to the order on backtracking and about every variable participating in
+// conflict analysis ('bump'), once per conflict ('decay').
+trait VarOrder: Send {
+    fn init_var(&mut self, v: Var, activity: f64);
+    fn insert(&mut self, v: Var);
+    fn bump(&mut self, v: Var);
+    fn decay(&mut self);
+    fn rebuild(&mut self, vars: Vec<Var>);
+    fn random_var(&self, rand: &mut random::Random) -> Option<Var>;
+    fn pick(&mut self, is_free: &dyn Fn(Var) -> bool) -> Option<Var>;
+
+    fn on_assign(&mut self, _: Var) {}
+
+    fn on_unassign(&mut self, v: Var) {
+        self.insert(v);
+    }
+
+    // Whether 'on_assign' has to be called for every assignment on the trail.
+    fn tracks_assignments(&self) -> bool {
+        false
+    }
+
+    // Variables with larger scores come first. 'restore' rebuilds an equivalent order from the scores
+    // of all variables (for checkpoints).
+    fn score(&self, v: Var) -> f64;
+    fn restore(&mut self, scores: &[(Var, f64)]);
+
+    fn clone_box(&self) -> Box<dyn VarOrder>;
+
+    fn branching(&self) -> Branching;
+}
+
+impl Clone for Box<dyn VarOrder> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+
+#[derive(Clone, Copy, Debug)]
 pub struct DecisionHeuristicSettings {
+    pub branching: Branching,
+    pub stable_branching: Branching, // Branching heuristic of the stable mode.
     pub var_decay: f64,
     pub random_seed: f64,
     pub random_var_freq: f64,
     pub phase_saving: PhaseSaving, // Controls the level of phase saving
     pub rnd_pol: bool,             // Use random polarities for branching heuristics.
     pub rnd_init_act: bool,        // Initialize variable activities with a small random value.
+    pub target_phase: bool,        // Prefer the phases of the largest conflict-free assignment in the stable mode.
 }
 
 impl Default for DecisionHeuristicSettings {
     fn default() -> Self {
         DecisionHeuristicSettings {
+            branching: Branching::Vsids,
+            stable_branching: Branching::Vsids,
             var_decay: 0.95,
             random_seed: 91648253.0,
             random_var_freq: 0.0,
             phase_saving: PhaseSaving::Full,
             rnd_pol: false,
             rnd_init_act: false,
+            target_phase: true,
         }
     }
 }
 
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct VarLine {
     polarity: bool,         // The preferred polarity of each variable.
     user_pol: Option<bool>, // The users preferred polarity of each variable.
     decision: bool, // Declares if a variable is eligible for selection in the decision heuristic.
+    target: Option<bool>, // The phase in the largest conflict-free assignment since the last restart.
+    best: Option<bool>,   // The phase in the largest conflict-free assignment since the last rephasing.
 }
 
+#[derive(Clone)]
 pub struct DecisionHeuristic {
     settings: DecisionHeuristicSettings,
-    var_inc: f64, // Amount to bump next variable with.
     rand: random::Random,
     var: VarMap<VarLine>,
-    activity: VarMap<f64>,
-    queue: VarHeap, // A priority queue of variables ordered with respect to the variable activity.
+    order: Box<dyn VarOrder>,
+    inactive: Option<Box<dyn VarOrder>>, // Order of the other mode, if it uses a different heuristic.
+    assigned_head: usize, // Length of the trail prefix already reported to 'order'.
+    stable: bool,
+    target_assigned: usize,
+    best_assigned: usize,
+    pub track_best: bool, // Remember the best phases (needed for rephasing).
 
     pub dec_vars: usize,
     pub rnd_decisions: u64,
@@ -55,33 +134,42 @@ pub struct DecisionHeuristic {
 impl DecisionHeuristic {
     pub fn new(settings: DecisionHeuristicSettings) -> DecisionHeuristic {
         let seed = settings.random_seed;
+        let order = new_order(settings.branching, settings.var_decay);
         DecisionHeuristic {
             settings,
-            var_inc: 1.0,
             rand: random::Random::new(seed),
             var: VarMap::new(),
-            activity: VarMap::new(),
-            queue: VarHeap::new(),
+            order,
+            inactive: None,
+            assigned_head: 0,
+            stable: false,
+            target_assigned: 0,
+            best_assigned: 0,
+            track_best: false,
             dec_vars: 0,
             rnd_decisions: 0,
         }
     }
 
     pub fn init_var(&mut self, v: Var, upol: Option<bool>, dvar: bool) {
-        self.activity.insert(
-            &v,
+        let activity =
             if self.settings.rnd_init_act {
                 self.rand.drand() * 0.00001
             } else {
                 0.0
-            },
-        );
+            };
+        self.order.init_var(v, activity);
+        if let Some(ref mut order) = self.inactive {
+            order.init_var(v, activity);
+        }
         self.var.insert(
             &v,
             VarLine {
                 polarity: true,
                 user_pol: upol,
                 decision: false,
+                target: None,
+                best: None,
             },
         );
         self.set_decision_var(v, dvar);
@@ -92,8 +180,7 @@ impl DecisionHeuristic {
         if b != ln.decision {
             if b {
                 self.dec_vars += 1;
-                let ref act = self.activity;
-                self.queue.insert(v, |a, b| act[a] > act[b]);
+                self.order.insert(v);
             } else {
                 self.dec_vars -= 1;
             }
@@ -101,6 +188,10 @@ impl DecisionHeuristic {
         }
     }
 
+    pub fn is_decision(&self, v: Var) -> bool {
+        self.var[&v].decision
+    }
+
     #[inline]
     pub fn save_phase(&mut self, lit: Lit, top_level: bool) {
         let ref mut ln = self.var[&lit.var()];
@@ -115,67 +206,202 @@ impl DecisionHeuristic {
         }
     }
 
+    // Switches between the focused and the stable mode. Must be called at the ground level.
+    pub fn set_stable(&mut self, stable: bool, assigns: &Assignment) {
+        if stable == self.stable {
+            return;
+        }
+
+        self.stable = stable;
+        self.target_assigned = 0;
+        if self.order.branching() != self.mode_branching(stable) {
+            let order =
+                match self.inactive.take() {
+                    Some(order) => order,
+                    None => self.new_mode_order(stable),
+                };
+
+            self.inactive = Some(mem::replace(&mut self.order, order));
+            self.assigned_head = 0;
+            self.rebuild_order_heap(assigns);
+        }
+    }
+
+    // Remembers the phases of a conflict-free assignment if it's the largest one so far.
+    pub fn update_phases(&mut self, trail: &[Lit]) {
+        if self.stable && self.settings.target_phase && trail.len() > self.target_assigned {
+            for &lit in trail.iter() {
+                self.var[&lit.var()].target = Some(lit.sign());
+            }
+            self.target_assigned = trail.len();
+        }
+
+        if self.track_best && trail.len() > self.best_assigned {
+            for &lit in trail.iter() {
+                self.var[&lit.var()].best = Some(lit.sign());
+            }
+            self.best_assigned = trail.len();
+        }
+    }
+
+    pub fn reset_target(&mut self) {
+        self.target_assigned = 0;
+    }
+
+    // Overwrites saved phases of all variables.
+    pub fn rephase(&mut self, kind: Rephase) {
+        for (_, ln) in self.var.iter_mut() {
+            ln.polarity =
+                match kind {
+                    Rephase::Original => true,
+                    Rephase::Inverted => false,
+                    Rephase::Best => ln.best.unwrap_or(ln.polarity),
+                    Rephase::Random => self.rand.chance(0.5),
+                };
+            ln.target = None;
+        }
+
+        self.target_assigned = 0;
+        self.best_assigned = 0;
+    }
+
+    pub fn phase(&self, v: Var) -> bool {
+        self.var[&v].polarity
+    }
+
+    pub fn set_phase(&mut self, lit: Lit) {
+        self.var[&lit.var()].polarity = lit.sign();
+    }
+
     pub fn try_return_var(&mut self, var: Var) {
-        let ref mut ln = self.var[&var];
-        if ln.decision {
-            let ref act = self.activity;
-            self.queue.insert(var, |a, b| act[a] > act[b]);
+        if self.var[&var].decision {
+            self.order.on_unassign(var);
         }
     }
 
-    pub fn bump_activity(&mut self, v: &Var) {
-        let new = self.activity[v] + self.var_inc;
-        if new > 1e100 {
-            self.var_inc *= 1e-100;
-            for (_, act) in self.activity.iter_mut() {
-                *act *= 1e-100;
+    // Reports assignments made since the last call to the heuristics that keep track of them.
+    pub fn sync_assigned(&mut self, assigns: &Assignment) {
+        if self.order.tracks_assignments() {
+            let trail = assigns.trail();
+            for &lit in trail[self.assigned_head.min(trail.len())..].iter() {
+                self.order.on_assign(lit.var());
             }
-            self.activity[v] = new * 1e-100;
-        } else {
-            self.activity[v] = new;
+            self.assigned_head = trail.len();
         }
+    }
 
-        let ref act = self.activity;
-        self.queue.update(v, |a, b| act[a] > act[b]);
+    // Forgets assignments beyond the first 'len' trail entries.
+    pub fn backtrack_to(&mut self, len: usize) {
+        self.assigned_head = self.assigned_head.min(len);
+    }
+
+    pub fn bump_activity(&mut self, v: &Var) {
+        self.order.bump(*v);
     }
 
     pub fn decay_activity(&mut self) {
-        self.var_inc *= 1.0 / self.settings.var_decay;
+        self.order.decay();
     }
 
     pub fn rebuild_order_heap(&mut self, assigns: &Assignment) {
-        let mut tmp = Vec::with_capacity(self.queue.len());
+        let mut tmp = Vec::new();
         for (v, vl) in self.var.iter() {
             if vl.decision && assigns.is_undef(v) {
                 tmp.push(v);
             }
         }
 
-        let ref act = self.activity;
-        self.queue.heapify_from(tmp, |a, b| act[a] > act[b]);
+        self.order.rebuild(tmp);
     }
 
     fn pick_branch_var(&mut self, assigns: &Assignment) -> Option<Var> {
         // Random decision:
-        if self.rand.chance(self.settings.random_var_freq) && !self.queue.is_empty() {
-            let v = self.queue[self.rand.irand(self.queue.len())];
-            if assigns.is_undef(v) && self.var[&v].decision {
-                self.rnd_decisions += 1;
-                return Some(v);
+        if self.rand.chance(self.settings.random_var_freq) {
+            if let Some(v) = self.order.random_var(&mut self.rand) {
+                if assigns.is_undef(v) && self.var[&v].decision {
+                    self.rnd_decisions += 1;
+                    return Some(v);
+                }
             }
         }
 
-        // Activity based decision:
-        while let Some(v) = {
-            let ref act = self.activity;
-            self.queue.pop(|a, b| act[a] > act[b])
-        } {
-            if assigns.is_undef(v) && self.var[&v].decision {
-                return Some(v);
+        // Heuristic based decision:
+        let ref var = self.var;
+        self.order.pick(&|v| assigns.is_undef(v) && var[&v].decision)
+    }
+
+    fn mode_branching(&self, stable: bool) -> Branching {
+        if stable { self.settings.stable_branching } else { self.settings.branching }
+    }
+
+    // Order of the mode 'stable' with all variables, without scores.
+    fn new_mode_order(&self, stable: bool) -> Box<dyn VarOrder> {
+        let mut order = new_order(self.mode_branching(stable), self.settings.var_decay);
+        for (v, _) in self.var.iter() {
+            order.init_var(v, 0.0);
+        }
+        order
+    }
+
+    pub fn write_checkpoint<W: io::Write>(&self, w: &mut CheckpointWriter<W>) -> io::Result<()> {
+        w.f64(self.rand.seed())?;
+        w.u64(self.rnd_decisions)?;
+        w.u64(self.stable as u64)?;
+        w.usize(self.target_assigned)?;
+        w.usize(self.best_assigned)?;
+        for (v, ln) in self.var.iter() {
+            w.u64(ln.decision as u64)?;
+            w.option_bool(ln.user_pol)?;
+            w.u64(ln.polarity as u64)?;
+            w.option_bool(ln.target)?;
+            w.option_bool(ln.best)?;
+            w.f64(self.order.score(v))?;
+        }
+
+        // Scores of the other mode, if it has an order of its own:
+        w.u64(self.inactive.is_some() as u64)?;
+        if let Some(ref order) = self.inactive {
+            for (v, _) in self.var.iter() {
+                w.f64(order.score(v))?;
             }
         }
+        Ok(())
+    }
+
+    // The variables are created already, the order must be rebuilt afterwards.
+    pub fn read_checkpoint<R: io::Read>(&mut self, r: &mut CheckpointReader<R>, vars: usize) -> io::Result<()> {
+        self.rand = random::Random::new(r.f64()?);
+        self.rnd_decisions = r.u64()?;
+        self.stable = r.u64()? != 0;
+        self.target_assigned = r.usize()?;
+        self.best_assigned = r.usize()?;
+        let mut scores = Vec::new();
+        for i in 0..vars {
+            let v = Var::unidx(i);
+            self.set_decision_var(v, r.u64()? != 0);
+            let ln = &mut self.var[&v];
+            ln.user_pol = r.option_bool()?;
+            ln.polarity = r.u64()? != 0;
+            ln.target = r.option_bool()?;
+            ln.best = r.option_bool()?;
+            scores.push((v, r.f64()?));
+        }
 
-        None
+        if self.stable && self.settings.stable_branching != self.settings.branching {
+            self.order = self.new_mode_order(true);
+        }
+        self.order.restore(&scores);
+
+        self.inactive =
+            if r.u64()? != 0 {
+                let mut order = self.new_mode_order(!self.stable);
+                let scores: Vec<(Var, f64)> = (0..vars).map(|i| Ok((Var::unidx(i), r.f64()?))).collect::<io::Result<_>>()?;
+                order.restore(&scores);
+                Some(order)
+            } else {
+                None
+            };
+        Ok(())
     }
 
     pub fn pick_branch_lit(&mut self, assigns: &Assignment) -> Option<Lit> {
@@ -185,9 +411,41 @@ impl DecisionHeuristic {
             let sign = match ln.user_pol {
                 Some(s) => s,
                 None if self.settings.rnd_pol => self.rand.chance(0.5),
+                None if self.stable && self.settings.target_phase => ln.target.unwrap_or(ln.polarity),
                 None => ln.polarity,
             };
             v.sign_lit(sign)
         })
     }
 }
+
+
+fn new_order(branching: Branching, var_decay: f64) -> Box<dyn VarOrder> {
+    match branching {
+        Branching::Vsids => Box::new(vsids::Vsids::new(var_decay)),
+        Branching::Vmtf => Box::new(vmtf::Vmtf::new()),
+        Branching::Lrb => Box::new(lrb::LearningRate::new(lrb::Reward::LearningRate)),
+        Branching::Chb => Box::new(lrb::LearningRate::new(lrb::Reward::History)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branching() {
+        let assigns = Assignment::new();
+        for &branching in [Branching::Vsids, Branching::Vmtf, Branching::Lrb, Branching::Chb].iter() {
+            let heur = DecisionHeuristic::new(DecisionHeuristicSettings { branching, ..Default::default() });
+            assert_eq!(heur.order.branching(), branching);
+
+            let mut heur = DecisionHeuristic::new(DecisionHeuristicSettings { stable_branching: branching, ..Default::default() });
+            heur.set_stable(true, &assigns);
+            assert_eq!(heur.order.branching(), branching);
+            heur.set_stable(false, &assigns);
+            assert_eq!(heur.order.branching(), Branching::Vsids);
+        }
+    }
+}
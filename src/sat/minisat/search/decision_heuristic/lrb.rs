@@ -0,0 +1,161 @@
+use crate::sat::formula::{Var, VarHeap, VarMap};
+use super::{random, Branching, VarOrder};
+
+
+const ALPHA_INIT: f64 = 0.4;
+const ALPHA_DECAY: f64 = 1e-6;
+const ALPHA_MIN: f64 = 0.06;
+
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Reward {
+    LearningRate, // LRB: share of the conflicts the variable participated in while assigned.
+    History,      // CHB: reciprocal of the number of conflicts since the last participation.
+}
+
+
+#[derive(Clone)]
+struct VarLine {
+    assigned_at: u64,   // Number of conflicts when the variable was assigned.
+    participated: u64,  // Number of conflicts the variable participated in since then.
+    last_conflict: u64, // Number of conflicts when the variable participated last time.
+}
+
+
+// Branching as a multi-armed bandit: every variable has an exponential recency weighted average
+// 'q' of the rewards it received, updated with step size 'alpha' when the variable is unassigned.
+#[derive(Clone)]
+pub struct LearningRate {
+    reward: Reward,
+    alpha: f64,
+    conflicts: u64,
+    q: VarMap<f64>,
+    var: VarMap<VarLine>,
+    queue: VarHeap, // A priority queue of variables ordered with respect to 'q'.
+}
+
+impl LearningRate {
+    pub fn new(reward: Reward) -> LearningRate {
+        LearningRate {
+            reward,
+            alpha: ALPHA_INIT,
+            conflicts: 0,
+            q: VarMap::new(),
+            var: VarMap::new(),
+            queue: VarHeap::new(),
+        }
+    }
+}
+
+impl VarOrder for LearningRate {
+    fn init_var(&mut self, v: Var, activity: f64) {
+        self.q.insert(&v, activity);
+        self.var.insert(&v, VarLine { assigned_at: 0, participated: 0, last_conflict: 0 });
+    }
+
+    fn insert(&mut self, v: Var) {
+        let q = &self.q;
+        self.queue.insert(v, |a, b| q[a] > q[b]);
+    }
+
+    fn on_assign(&mut self, v: Var) {
+        let ln = &mut self.var[&v];
+        ln.assigned_at = self.conflicts;
+        ln.participated = 0;
+    }
+
+    fn on_unassign(&mut self, v: Var) {
+        let reward = {
+            let ln = &self.var[&v];
+            match self.reward {
+                Reward::LearningRate => {
+                    let interval = self.conflicts - ln.assigned_at;
+                    if interval > 0 {
+                        Some(ln.participated as f64 / interval as f64)
+                    } else {
+                        None
+                    }
+                }
+
+                Reward::History => {
+                    let multiplier = if ln.participated > 0 { 1.0 } else { 0.9 };
+                    Some(multiplier / (self.conflicts - ln.last_conflict + 1) as f64)
+                }
+            }
+        };
+
+        if let Some(r) = reward {
+            let q = &mut self.q[&v];
+            *q = (1.0 - self.alpha) * *q + self.alpha * r;
+        }
+
+        let q = &self.q;
+        if !self.queue.update(&v, |a, b| q[a] > q[b]) {
+            self.queue.insert(v, |a, b| q[a] > q[b]);
+        }
+    }
+
+    fn bump(&mut self, v: Var) {
+        let ln = &mut self.var[&v];
+        ln.participated += 1;
+        ln.last_conflict = self.conflicts;
+    }
+
+    fn decay(&mut self) {
+        self.conflicts += 1;
+        if self.alpha > ALPHA_MIN {
+            self.alpha -= ALPHA_DECAY;
+        }
+    }
+
+    fn rebuild(&mut self, vars: Vec<Var>) {
+        let q = &self.q;
+        self.queue.heapify_from(vars, |a, b| q[a] > q[b]);
+    }
+
+    fn random_var(&self, rand: &mut random::Random) -> Option<Var> {
+        if self.queue.is_empty() {
+            None
+        } else {
+            Some(self.queue[rand.irand(self.queue.len())])
+        }
+    }
+
+    fn pick(&mut self, is_free: &dyn Fn(Var) -> bool) -> Option<Var> {
+        while let Some(v) = {
+            let q = &self.q;
+            self.queue.pop(|a, b| q[a] > q[b])
+        } {
+            if is_free(v) {
+                return Some(v);
+            }
+        }
+
+        None
+    }
+
+    fn tracks_assignments(&self) -> bool {
+        true
+    }
+
+    fn score(&self, v: Var) -> f64 {
+        self.q[&v]
+    }
+
+    fn restore(&mut self, scores: &[(Var, f64)]) {
+        for &(v, score) in scores.iter() {
+            self.q[&v] = score;
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn VarOrder> {
+        Box::new(self.clone())
+    }
+
+    fn branching(&self) -> Branching {
+        match self.reward {
+            Reward::LearningRate => Branching::Lrb,
+            Reward::History => Branching::Chb,
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::sat::formula::{Var, VarMap};
+use super::{random, Branching, VarOrder};
+
+
+#[derive(Clone)]
+struct Link {
+    prev: Option<Var>, // Previously bumped variable.
+    next: Option<Var>, // Next bumped variable.
+    stamp: u64,        // Time of the last bump.
+}
+
+
+// Variable move-to-front: variables are kept in a queue ordered by the time of their last bump.
+// Every variable after 'search' is assigned, so decisions start looking from there.
+#[derive(Clone)]
+pub struct Vmtf {
+    links: VarMap<Link>,
+    vars: Vec<Var>,
+    first: Option<Var>,
+    last: Option<Var>,
+    search: Option<Var>,
+    stamp: u64,
+    bumped: Vec<Var>, // Variables bumped during the current conflict analysis.
+}
+
+impl Vmtf {
+    pub fn new() -> Vmtf {
+        Vmtf {
+            links: VarMap::new(),
+            vars: Vec::new(),
+            first: None,
+            last: None,
+            search: None,
+            stamp: 0,
+            bumped: Vec::new(),
+        }
+    }
+
+    fn dequeue(&mut self, v: Var) {
+        let (prev, next) = {
+            let link = &self.links[&v];
+            (link.prev, link.next)
+        };
+
+        match prev {
+            Some(p) => { self.links[&p].next = next; }
+            None => { self.first = next; }
+        }
+
+        match next {
+            Some(n) => { self.links[&n].prev = prev; }
+            None => { self.last = prev; }
+        }
+
+        if self.search == Some(v) {
+            self.search = prev.or(next);
+        }
+    }
+
+    fn enqueue(&mut self, v: Var) {
+        self.stamp += 1;
+        {
+            let link = &mut self.links[&v];
+            link.prev = self.last;
+            link.next = None;
+            link.stamp = self.stamp;
+        }
+
+        match self.last {
+            Some(l) => { self.links[&l].next = Some(v); }
+            None => { self.first = Some(v); }
+        }
+        self.last = Some(v);
+    }
+}
+
+impl VarOrder for Vmtf {
+    fn init_var(&mut self, v: Var, _: f64) {
+        self.links.insert(&v, Link { prev: None, next: None, stamp: 0 });
+        self.vars.push(v);
+        self.enqueue(v);
+        self.search = Some(v);
+    }
+
+    fn insert(&mut self, v: Var) {
+        match self.search {
+            Some(s) if self.links[&s].stamp >= self.links[&v].stamp => {}
+            _ => { self.search = Some(v); }
+        }
+    }
+
+    fn bump(&mut self, v: Var) {
+        self.bumped.push(v);
+    }
+
+    fn decay(&mut self) {
+        // Move bumped variables to the front keeping their relative order. They are all assigned, so
+        // 'search' doesn't have to be updated:
+        let mut bumped = std::mem::take(&mut self.bumped);
+        bumped.sort_by_key(|v| self.links[v].stamp);
+        for &v in bumped.iter() {
+            self.dequeue(v);
+            self.enqueue(v);
+        }
+
+        bumped.clear();
+        self.bumped = bumped;
+    }
+
+    fn rebuild(&mut self, _: Vec<Var>) {
+        self.search = self.last;
+    }
+
+    fn random_var(&self, rand: &mut random::Random) -> Option<Var> {
+        if self.vars.is_empty() {
+            None
+        } else {
+            Some(self.vars[rand.irand(self.vars.len())])
+        }
+    }
+
+    fn pick(&mut self, is_free: &dyn Fn(Var) -> bool) -> Option<Var> {
+        while let Some(v) = self.search {
+            if is_free(v) {
+                return Some(v);
+            }
+            self.search = self.links[&v].prev;
+        }
+
+        None
+    }
+
+    fn score(&self, v: Var) -> f64 {
+        self.links[&v].stamp as f64
+    }
+
+    fn restore(&mut self, scores: &[(Var, f64)]) {
+        let mut scores = scores.to_vec();
+        scores.sort_by(|x, y| x.1.partial_cmp(&y.1).unwrap());
+        for &(v, _) in scores.iter() {
+            self.dequeue(v);
+            self.enqueue(v);
+        }
+
+        // The saved stamps are distinct, so keeping them keeps the order:
+        for &(v, score) in scores.iter() {
+            self.links[&v].stamp = score as u64;
+            self.stamp = score as u64;
+        }
+        self.search = self.last;
+    }
+
+    fn clone_box(&self) -> Box<dyn VarOrder> {
+        Box::new(self.clone())
+    }
+
+    fn branching(&self) -> Branching {
+        Branching::Vmtf
+    }
+}
@@ -0,0 +1,101 @@
+use crate::sat::formula::{Var, VarHeap, VarMap};
+use super::{random, Branching, VarOrder};
+
+
+// Variable state independent decaying sum: variables are ordered by activity, which is bumped on
+// every participation in conflict analysis and decays geometrically.
+#[derive(Clone)]
+pub struct Vsids {
+    var_decay: f64,
+    var_inc: f64, // Amount to bump next variable with.
+    activity: VarMap<f64>,
+    queue: VarHeap, // A priority queue of variables ordered with respect to the variable activity.
+}
+
+impl Vsids {
+    pub fn new(var_decay: f64) -> Vsids {
+        Vsids {
+            var_decay,
+            var_inc: 1.0,
+            activity: VarMap::new(),
+            queue: VarHeap::new(),
+        }
+    }
+}
+
+impl VarOrder for Vsids {
+    fn init_var(&mut self, v: Var, activity: f64) {
+        self.activity.insert(&v, activity);
+    }
+
+    fn insert(&mut self, v: Var) {
+        let ref act = self.activity;
+        self.queue.insert(v, |a, b| act[a] > act[b]);
+    }
+
+    fn bump(&mut self, v: Var) {
+        let new = self.activity[&v] + self.var_inc;
+        if new > 1e100 {
+            self.var_inc *= 1e-100;
+            for (_, act) in self.activity.iter_mut() {
+                *act *= 1e-100;
+            }
+            self.activity[&v] = new * 1e-100;
+        } else {
+            self.activity[&v] = new;
+        }
+
+        let ref act = self.activity;
+        self.queue.update(&v, |a, b| act[a] > act[b]);
+    }
+
+    fn decay(&mut self) {
+        self.var_inc *= 1.0 / self.var_decay;
+    }
+
+    fn rebuild(&mut self, vars: Vec<Var>) {
+        let ref act = self.activity;
+        self.queue.heapify_from(vars, |a, b| act[a] > act[b]);
+    }
+
+    fn random_var(&self, rand: &mut random::Random) -> Option<Var> {
+        if self.queue.is_empty() {
+            None
+        } else {
+            Some(self.queue[rand.irand(self.queue.len())])
+        }
+    }
+
+    fn pick(&mut self, is_free: &dyn Fn(Var) -> bool) -> Option<Var> {
+        while let Some(v) = {
+            let ref act = self.activity;
+            self.queue.pop(|a, b| act[a] > act[b])
+        } {
+            if is_free(v) {
+                return Some(v);
+            }
+        }
+
+        None
+    }
+
+    // Activities relative to the next bump:
+    fn score(&self, v: Var) -> f64 {
+        self.activity[&v] / self.var_inc
+    }
+
+    fn restore(&mut self, scores: &[(Var, f64)]) {
+        self.var_inc = 1.0;
+        for &(v, score) in scores.iter() {
+            self.activity[&v] = score;
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn VarOrder> {
+        Box::new(self.clone())
+    }
+
+    fn branching(&self) -> Branching {
+        Branching::Vsids
+    }
+}
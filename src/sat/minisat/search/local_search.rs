@@ -0,0 +1,198 @@
+use crate::sat::formula::{assignment::Assignment, clause::*, Idx, Lit, Var};
+use crate::sat::minisat::budget::Budget;
+use super::random::Random;
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LocalSearchAlgorithm {
+    ProbSat, // Flip a variable of a falsified clause with probability decreasing with its break count.
+    WalkSat, // Flip a variable of a falsified clause with the least break count, or a random one.
+}
+
+
+const PROBSAT_CB: f64 = 2.06;
+const PROBSAT_EPS: f64 = 0.9;
+const WALKSAT_NOISE: f64 = 0.567;
+
+// Flips between two checks of the asynchronous interrupt:
+const INTERRUPT_CHECK: u64 = 1 << 12;
+
+
+// Stochastic local search over the problem clauses not satisfied at the ground level. An assignment
+// is a sign per variable: the literal 'v.sign_lit(phase[v])' is true.
+pub struct LocalSearch {
+    algorithm: LocalSearchAlgorithm,
+    clauses: Vec<Vec<Lit>>,
+    occurs: Vec<Vec<usize>>, // Clauses containing a literal.
+    true_count: Vec<usize>,  // Number of true literals of every clause.
+    unsat: Vec<usize>,       // Falsified clauses.
+    unsat_pos: Vec<usize>,   // Position of falsified clauses in 'unsat'.
+    vars: Vec<Var>,
+    phase: Vec<bool>,
+    best: Vec<bool>,
+    scores: Vec<f64>,
+    pub flips: u64,
+}
+
+impl LocalSearch {
+    // Must be called at the ground level. Literals false at the ground level are left out.
+    pub fn new(algorithm: LocalSearchAlgorithm, ca: &ClauseAllocator, clauses: &[ClauseRef], assigns: &Assignment) -> LocalSearch {
+        let mut ls = LocalSearch {
+            algorithm,
+            clauses: Vec::new(),
+            occurs: vec![Vec::new(); 2 * assigns.number_of_vars()],
+            true_count: Vec::new(),
+            unsat: Vec::new(),
+            unsat_pos: Vec::new(),
+            vars: Vec::new(),
+            phase: vec![false; assigns.number_of_vars()],
+            best: vec![false; assigns.number_of_vars()],
+            scores: Vec::new(),
+            flips: 0,
+        };
+
+        let mut seen = vec![false; assigns.number_of_vars()];
+        for &cr in clauses.iter() {
+            let lits = ca.literals(cr);
+            if lits.iter().any(|&lit| assigns.is_assigned_pos(lit)) {
+                continue;
+            }
+
+            let ci = ls.clauses.len();
+            let clause: Vec<Lit> = lits.iter().cloned().filter(|&lit| !assigns.is_assigned_neg(lit)).collect();
+            for &lit in clause.iter() {
+                ls.occurs[lit.idx()].push(ci);
+                if !seen[lit.var().idx()] {
+                    seen[lit.var().idx()] = true;
+                    ls.vars.push(lit.var());
+                }
+            }
+            ls.clauses.push(clause);
+        }
+
+        ls.true_count = vec![0; ls.clauses.len()];
+        ls.unsat_pos = vec![0; ls.clauses.len()];
+        ls
+    }
+
+    // Sets the starting assignment.
+    pub fn init<F: FnMut(Var) -> bool>(&mut self, mut initial: F) {
+        for &v in self.vars.iter() {
+            self.phase[v.idx()] = initial(v);
+        }
+    }
+
+    // Runs until all clauses are satisfied or 'max_flips' flips are made. Returns the number of
+    // falsified clauses in the best assignment found.
+    pub fn run(&mut self, max_flips: u64, rand: &mut Random, budget: &Budget) -> usize {
+        self.unsat.clear();
+        for ci in 0..self.clauses.len() {
+            let phase = &self.phase;
+            self.true_count[ci] = self.clauses[ci].iter().filter(|lit| lit.sign() == phase[lit.var().idx()]).count();
+            if self.true_count[ci] == 0 {
+                self.unsat_pos[ci] = self.unsat.len();
+                self.unsat.push(ci);
+            }
+        }
+
+        self.best.copy_from_slice(&self.phase);
+        let mut best_unsat = self.unsat.len();
+
+        let mut flips = 0;
+        while !self.unsat.is_empty() && flips < max_flips {
+            if flips % INTERRUPT_CHECK == 0 && budget.interrupted_now() {
+                break;
+            }
+
+            let ci = self.unsat[rand.irand(self.unsat.len())];
+            let v = self.pick(ci, rand);
+            self.flip(v);
+            flips += 1;
+
+            if self.unsat.len() < best_unsat {
+                best_unsat = self.unsat.len();
+                self.best.copy_from_slice(&self.phase);
+            }
+        }
+
+        self.flips += flips;
+        best_unsat
+    }
+
+    // Literals of the best assignment found by the last run.
+    pub fn best<'a>(&'a self) -> impl Iterator<Item = Lit> + 'a {
+        self.vars.iter().map(move |&v| v.sign_lit(self.best[v.idx()]))
+    }
+
+    // Number of clauses that become falsified by flipping 'v'.
+    fn break_count(&self, v: Var) -> usize {
+        let lit = v.sign_lit(self.phase[v.idx()]);
+        self.occurs[lit.idx()].iter().filter(|&&ci| self.true_count[ci] == 1).count()
+    }
+
+    fn pick(&mut self, ci: usize, rand: &mut Random) -> Var {
+        let mut scores = std::mem::take(&mut self.scores);
+        scores.clear();
+        for &lit in self.clauses[ci].iter() {
+            scores.push(self.break_count(lit.var()) as f64);
+        }
+
+        let clause = &self.clauses[ci];
+        let i =
+            match self.algorithm {
+                LocalSearchAlgorithm::ProbSat => {
+                    let mut sum = 0.0;
+                    for score in scores.iter_mut() {
+                        *score = (PROBSAT_EPS + *score).powf(-PROBSAT_CB);
+                        sum += *score;
+                    }
+
+                    let mut x = rand.drand() * sum;
+                    let mut i = 0;
+                    while i + 1 < scores.len() && x >= scores[i] {
+                        x -= scores[i];
+                        i += 1;
+                    }
+                    i
+                }
+
+                LocalSearchAlgorithm::WalkSat => {
+                    let min = scores.iter().cloned().fold(f64::INFINITY, f64::min);
+                    if min > 0.0 && rand.chance(WALKSAT_NOISE) {
+                        rand.irand(clause.len())
+                    } else {
+                        let ties: Vec<usize> = (0..scores.len()).filter(|&i| scores[i] == min).collect();
+                        ties[rand.irand(ties.len())]
+                    }
+                }
+            };
+
+        self.scores = scores;
+        clause[i].var()
+    }
+
+    fn flip(&mut self, v: Var) {
+        let was_true = v.sign_lit(self.phase[v.idx()]);
+        self.phase[v.idx()] = !self.phase[v.idx()];
+
+        for &ci in self.occurs[(!was_true).idx()].iter() {
+            self.true_count[ci] += 1;
+            if self.true_count[ci] == 1 {
+                let pos = self.unsat_pos[ci];
+                let last = self.unsat.pop().unwrap();
+                if last != ci {
+                    self.unsat[pos] = last;
+                    self.unsat_pos[last] = pos;
+                }
+            }
+        }
+
+        for &ci in self.occurs[was_true.idx()].iter() {
+            self.true_count[ci] -= 1;
+            if self.true_count[ci] == 0 {
+                self.unsat_pos[ci] = self.unsat.len();
+                self.unsat.push(ci);
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::sat::formula::{assignment::*, Idx, Lit, Var};
+use super::Searcher;
+
+
+#[derive(Clone, Copy, Debug)]
+pub struct CubeSettings {
+    pub depth: usize, // Maximal number of decisions in a cube.
+    pub min_free: f64, // Don't split nodes with less than this fraction of variables unassigned.
+    pub candidates: usize, // Number of variables looked ahead on in every node.
+}
+
+impl Default for CubeSettings {
+    fn default() -> Self {
+        CubeSettings {
+            depth: 10,
+            min_free: 0.0,
+            candidates: 100,
+        }
+    }
+}
+
+
+enum Lookahead {
+    Conflict,
+    Branch(Option<Lit>),
+}
+
+
+impl Searcher {
+    // Description:
+    //   Splits the formula into cubes by a decision tree. Every node branches on the variable whose
+    //   both polarities assign the most variables by unit propagation (lookahead), literals whose
+    //   propagation fails are implied in the node. The tree is built until the cutoff.
+    //
+    // Output:
+    //   Cubes not refuted by propagation. Any model of the formula satisfies one of them, so no
+    //   cubes means the formula is unsatisfiable.
+    pub fn cube(&mut self, settings: &CubeSettings) -> Vec<Vec<Lit>> {
+        assert!(self.bt.is_ground_level());
+
+        let mut cubes = Vec::new();
+        if self.bt.propagate().is_none() {
+            let weights = self.occurrence_weights();
+            self.cube_node(settings, &weights, &mut Vec::new(), &mut cubes);
+            self.cancel_until(GROUND_LEVEL);
+        }
+
+        trace!("Cubing: {} cubes", cubes.len());
+        cubes
+    }
+
+    // The node is propagated without a conflict.
+    fn cube_node(&mut self, settings: &CubeSettings, weights: &[f64], cube: &mut Vec<Lit>, cubes: &mut Vec<Vec<Lit>>) {
+        let vars = self.bt.assigns.number_of_vars();
+        let free = vars - self.bt.assigns.number_of_assigns();
+        if cube.len() >= settings.depth || (free as f64) < settings.min_free * (vars as f64) {
+            cubes.push(cube.clone());
+            return;
+        }
+
+        let branch =
+            match self.lookahead(settings, weights) {
+                Lookahead::Conflict => { return; }
+                Lookahead::Branch(None) => {
+                    cubes.push(cube.clone());
+                    return;
+                }
+                Lookahead::Branch(Some(lit)) => lit,
+            };
+
+        for &lit in [branch, !branch].iter() {
+            let level = self.bt.assigns.current_level();
+            self.bt.push_decision(lit);
+            if self.bt.propagate().is_none() {
+                cube.push(lit);
+                self.cube_node(settings, weights, cube, cubes);
+                cube.pop();
+            }
+            self.cancel_until(level);
+        }
+    }
+
+    // Looks ahead on the best candidate variables, assigning failed literals, and returns the best
+    // branching literal (none if all decision variables are assigned).
+    fn lookahead(&mut self, settings: &CubeSettings, weights: &[f64]) -> Lookahead {
+        loop {
+            let candidates = {
+                let assigns = &self.bt.assigns;
+                let heur = &self.ctx.heur;
+                let mut candidates: Vec<Var> = (0..assigns.number_of_vars())
+                    .map(Var::unidx)
+                    .filter(|&v| assigns.is_undef(v) && heur.is_decision(v))
+                    .collect();
+                candidates.sort_by(|x, y| weights[y.idx()].partial_cmp(&weights[x.idx()]).unwrap());
+                candidates.truncate(settings.candidates);
+                candidates
+            };
+
+            let mut best = None;
+            let mut failed = false;
+            for v in candidates {
+                if !self.bt.assigns.is_undef(v) {
+                    continue;
+                }
+
+                let implied =
+                    match (self.probe(v.pos_lit()), self.probe(v.neg_lit())) {
+                        (None, None) => { return Lookahead::Conflict; }
+                        (Some(_), None) => Some(v.pos_lit()),
+                        (None, Some(_)) => Some(v.neg_lit()),
+                        (Some(pos), Some(neg)) => {
+                            let score = pos * neg * 1024 + pos + neg;
+                            match best {
+                                Some((_, best_score)) if best_score >= score => {}
+                                _ => { best = Some((if pos >= neg { v.pos_lit() } else { v.neg_lit() }, score)); }
+                            }
+                            None
+                        }
+                    };
+
+                // The negation of a failed literal is implied in this node:
+                if let Some(lit) = implied {
+                    self.bt.assigns.assign_lit(lit, None);
+                    if self.bt.propagate().is_some() {
+                        return Lookahead::Conflict;
+                    }
+                    failed = true;
+                }
+            }
+
+            // Failed literals change the scores of the other candidates:
+            if !failed {
+                return Lookahead::Branch(best.map(|(lit, _)| lit));
+            }
+        }
+    }
+
+    // Returns the number of variables assigned by propagating 'lit', or none on a conflict.
+    fn probe(&mut self, lit: Lit) -> Option<u64> {
+        let level = self.bt.assigns.current_level();
+        let before = self.bt.assigns.number_of_assigns();
+        self.bt.push_decision(lit);
+        let res =
+            match self.bt.propagate() {
+                None => Some((self.bt.assigns.number_of_assigns() - before) as u64),
+                Some(_) => None,
+            };
+        self.cancel_until(level);
+        res
+    }
+
+    // Variables in many short problem clauses are considered first.
+    fn occurrence_weights(&self) -> Vec<f64> {
+        let mut weights = vec![0.0; self.bt.assigns.number_of_vars()];
+        for &cr in self.ctx.db.clauses().iter() {
+            let c = self.bt.ca.view(cr);
+            if !c.is_deleted() {
+                let weight = 0.5f64.powi(c.len() as i32);
+                for lit in c.lits() {
+                    weights[lit.var().idx()] += weight;
+                }
+            }
+        }
+        weights
+    }
+}
@@ -0,0 +1,54 @@
+use super::util::progress_estimate;
+use super::{BacktrackableFormula, SearchCtx};
+
+
+// Receives the events of a running search, e.g. to show its progress. All methods are called from
+// the search itself, so they should return quickly.
+pub trait Observer: Send {
+    fn restarted(&mut self, _: &Progress) {}
+
+    // A clause of 'len' literals (with 'lbd') was learnt.
+    fn conflict(&mut self, _len: usize, _lbd: u32) {}
+
+    // The learnt clauses were reduced from 'before' to 'after'.
+    fn reduced(&mut self, _before: usize, _after: usize) {}
+
+    // The clause arena was compacted from 'before' to 'after' bytes.
+    fn garbage_collected(&mut self, _before: usize, _after: usize) {}
+
+    // Clauses satisfied at the ground level were removed, or the preprocessing decreased the number
+    // of clauses by 'removed'.
+    fn simplified(&mut self, _removed: usize) {}
+
+    // Periodic snapshot, at the lines of the search statistics table.
+    fn progress(&mut self, _: &Progress) {}
+}
+
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Progress {
+    pub conflicts: u64,
+    pub decisions: u64,
+    pub propagations: u64,
+    pub restarts: u64,
+    pub free_vars: usize,
+    pub clauses: usize,
+    pub learnts: usize,
+    pub estimate: f64, // Fraction of the search space covered (see 'progress_estimate').
+}
+
+
+impl SearchCtx {
+    pub(super) fn progress(&self, bt: &BacktrackableFormula) -> Progress {
+        Progress {
+            conflicts: self.stats.conflicts,
+            decisions: self.stats.decisions,
+            propagations: bt.propagations(),
+            restarts: self.stats.starts,
+            free_vars: self.heur.dec_vars - bt.assigns.number_of_ground_assigns(),
+            clauses: self.db.stats.num_clauses,
+            learnts: self.db.stats.num_learnts,
+            estimate: progress_estimate(&bt.assigns),
+        }
+    }
+}
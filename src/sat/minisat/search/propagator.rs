@@ -0,0 +1,269 @@
+use crate::sat::formula::{assignment::*, clause::*, Lit};
+use super::conflict::Reason;
+use super::{AddClauseRes, Searcher};
+
+
+// External reasoning taking part in the search (CDCL(T), lazy clause generation). The searcher
+// notifies the propagator of its trail, i.e. of every assigned literal in the trail order, of new
+// decision levels and of backtracks, so that the propagator can mirror the assignment. Literals
+// implied by the propagator are explained by reason clauses only when the conflict analysis needs it.
+pub trait Propagator: Send {
+    // 'lit' became true.
+    fn assigned(&mut self, lit: Lit);
+
+    // A new decision level starts, the literals notified next are assigned at it.
+    fn decided(&mut self) {}
+
+    // Everything notified after the first 'level' decisions is unassigned. Literals implied at lower
+    // levels out of order (chronological backtracking) are kept, but they are notified again.
+    fn backtracked(&mut self, level: usize);
+
+    // Called when unit propagation reaches a fixpoint.
+    fn propagate(&mut self) -> Propagation;
+
+    // Explains an implied literal: returns a clause of 'lit' and literals that were false before
+    // 'lit' was implied.
+    fn reason(&mut self, lit: Lit) -> Vec<Lit>;
+
+    // Called with a complete assignment. The returned clauses are added to the formula and the search
+    // goes on, no clauses accept the model.
+    fn check_model(&mut self, _: &[Lit]) -> Vec<Vec<Lit>> {
+        Vec::new()
+    }
+}
+
+
+pub enum Propagation {
+    Implied(Vec<Lit>), // Literals implied by the current assignment, none at a fixpoint.
+    Conflict(Vec<Lit>), // A clause falsified by the current assignment.
+}
+
+
+// Called with every complete assignment, returns clauses refining the formula (none accept the model).
+pub type ModelRefinement = Box<dyn FnMut(&[Lit]) -> Vec<Vec<Lit>> + Send>;
+
+
+pub enum ModelCheck {
+    Accepted,
+    Refined, // Clauses were added, the search goes on from the ground level.
+    UnSAT,
+}
+
+
+pub struct External {
+    propagator: Box<dyn Propagator>,
+    notified: usize, // Length of the prefix of the trail the propagator knows.
+    levels: usize,   // Number of decision levels the propagator knows.
+    reasons: Vec<(Lit, ClauseRef)>, // Reasons allocated by the conflict analysis, not in the clause database yet.
+    facts: Vec<Lit>, // Literals explained by unit clauses, assigned at the next visit of the ground level.
+}
+
+impl External {
+    pub fn new(propagator: Box<dyn Propagator>) -> Self {
+        External {
+            propagator,
+            notified: 0,
+            levels: 0,
+            reasons: Vec::new(),
+            facts: Vec::new(),
+        }
+    }
+
+    fn notify(&mut self, assigns: &Assignment) {
+        let trail = assigns.trail();
+        for &lit in trail[self.notified..].iter() {
+            let level = assigns.level(lit.var()).offset_from_ground();
+            while self.levels < level {
+                self.propagator.decided();
+                self.levels += 1;
+            }
+            self.propagator.assigned(lit);
+        }
+        self.notified = trail.len();
+    }
+
+    // Must be called before the assignment backtracks to 'target_level'.
+    pub fn backtrack(&mut self, assigns: &Assignment, target_level: DecisionLevel) {
+        let level = target_level.offset_from_ground();
+        if self.levels > level {
+            self.propagator.backtracked(level);
+            self.levels = level;
+        }
+
+        let kept = assigns.trail().len() - assigns.trail_above(target_level).len();
+        if self.notified > kept {
+            self.notified = kept;
+        }
+    }
+
+    // Reason of the true literal 'lit' without a reason clause in the assignment. Reason clauses are
+    // allocated with 'lit' and the false literal of the highest level watched, like asserting learnt
+    // clauses, and left for 'SearchCtx::adopt_reasons'.
+    pub fn lazy_reason(&mut self, ca: &mut ClauseAllocator, assigns: &Assignment, lit: Lit) -> Reason {
+        if assigns.trail_at(assigns.level(lit.var())).first() == Some(&lit) {
+            return Reason::Decision;
+        }
+
+        let mut lits = self.propagator.reason(lit);
+        lits.sort();
+        lits.dedup();
+        let i = lits.iter().position(|&l| l == lit).expect("The reason clause doesn't contain the implied literal");
+        lits.swap(0, i);
+
+        for i in 2..lits.len() {
+            if assigns.level(lits[i].var()) > assigns.level(lits[1].var()) {
+                lits.swap(1, i);
+            }
+        }
+        debug_assert!(lits[1..].iter().all(|&l| assigns.is_assigned_neg(l)));
+
+        if lits.len() < 2 {
+            self.facts.push(lit);
+            return Reason::Fact;
+        }
+
+        let (_, cr) = ca.alloc(&lits, ClauseHeader::learnt(0.0, lits.len() as u32));
+        self.reasons.push((lit, cr));
+        Reason::Clause(cr)
+    }
+}
+
+
+impl Searcher {
+    pub fn set_propagator(&mut self, propagator: Box<dyn Propagator>) {
+        self.ctx.external = Some(External::new(propagator));
+    }
+
+    pub fn set_model_refinement(&mut self, refine: ModelRefinement) {
+        self.ctx.refine = Some(refine);
+    }
+
+    // Unit propagation interleaved with the external propagator until neither implies anything.
+    // Returns a conflict clause, or an error if the formula became unsatisfiable.
+    pub(super) fn propagate(&mut self) -> Result<Option<ClauseRef>, ()> {
+        loop {
+            if let Some(confl) = self.bt.propagate() {
+                return Ok(Some(confl));
+            }
+
+            let propagation =
+                match self.ctx.external {
+                    None => return Ok(None),
+                    Some(ref mut ext) => {
+                        if self.bt.is_ground_level() && !ext.facts.is_empty() {
+                            for lit in ext.facts.drain(..) {
+                                if self.bt.assigns.is_assigned_neg(lit) {
+                                    return Err(());
+                                } else if self.bt.assigns.is_undef(lit.var()) {
+                                    self.bt.assigns.assign_lit(lit, None);
+                                }
+                            }
+                            continue;
+                        }
+
+                        ext.notify(&self.bt.assigns);
+                        ext.propagator.propagate()
+                    }
+                };
+
+            match propagation {
+                Propagation::Implied(lits) => {
+                    let mut implied = false;
+                    for lit in lits {
+                        if self.bt.assigns.is_undef(lit.var()) {
+                            self.bt.assigns.assign_lit(lit, None);
+                            implied = true;
+                        } else if self.bt.assigns.is_assigned_neg(lit) {
+                            // The reason of a false literal is a conflict:
+                            let clause = self.ctx.external.as_mut().unwrap().propagator.reason(lit);
+                            return self.external_conflict(clause);
+                        }
+                    }
+
+                    if !implied {
+                        return Ok(None);
+                    }
+                }
+
+                Propagation::Conflict(clause) => {
+                    return self.external_conflict(clause);
+                }
+            }
+        }
+    }
+
+    fn external_conflict(&mut self, mut clause: Vec<Lit>) -> Result<Option<ClauseRef>, ()> {
+        clause.sort();
+        clause.dedup();
+        debug_assert!(clause.iter().all(|&lit| self.bt.assigns.is_assigned_neg(lit)));
+
+        match &clause[..] {
+            [] => Err(()),
+
+            [unit] => {
+                if self.bt.assigns.level(unit.var()).is_ground() {
+                    return Err(());
+                }
+                self.cancel_until(GROUND_LEVEL);
+                self.bt.assigns.assign_lit(*unit, None);
+                self.propagate()
+            }
+
+            lits => {
+                let cr = self.ctx.db.learn_clause(&mut self.bt.ca, lits, lits.len() as u32);
+                self.bt.attach(cr);
+                Ok(Some(cr))
+            }
+        }
+    }
+
+    // Passes the complete assignment to the external propagator, then to the model refinement.
+    pub(super) fn check_model(&mut self) -> ModelCheck {
+        let mut clauses = Vec::new();
+        if let Some(ref mut ext) = self.ctx.external {
+            ext.notify(&self.bt.assigns);
+            clauses = ext.propagator.check_model(self.bt.assigns.trail());
+        }
+
+        if clauses.is_empty() {
+            if let Some(ref mut refine) = self.ctx.refine {
+                clauses = refine(self.bt.assigns.trail());
+            }
+        }
+
+        if clauses.is_empty() {
+            return ModelCheck::Accepted;
+        }
+
+        self.cancel_until(GROUND_LEVEL);
+        for clause in clauses {
+            if let AddClauseRes::UnSAT = self.add_clause(&clause) {
+                return ModelCheck::UnSAT;
+            }
+        }
+        ModelCheck::Refined
+    }
+}
+
+
+impl super::SearchCtx {
+    // Moves the reasons allocated by the conflict analysis to the clause database.
+    pub(super) fn adopt_reasons(&mut self, bt: &mut super::BacktrackableFormula) {
+        if let Some(ref mut ext) = self.external {
+            for (lit, cr) in ext.reasons.drain(..) {
+                self.db.adopt_learnt(&mut bt.ca, cr);
+                bt.attach(cr);
+                bt.assigns.set_reason(lit, cr);
+            }
+        }
+    }
+}
+
+
+// Literals without reason clauses are decisions, unless the external propagator implied them.
+pub(super) fn lazy_reason(external: &mut Option<External>, ca: &mut ClauseAllocator, assigns: &Assignment, lit: Lit) -> Reason {
+    match *external {
+        Some(ref mut ext) => ext.lazy_reason(ca, assigns, lit),
+        None => Reason::Decision,
+    }
+}
@@ -0,0 +1,114 @@
+use crate::sat::formula::{assignment::*, Idx, Lit};
+use super::Searcher;
+
+
+// Unit propagation under assumptions ran into a conflict.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    pub clause: Vec<Lit>, // Clause falsified by the assumptions, empty if an assumption contradicts another one or a unit.
+    pub assumptions: Vec<Lit>, // Assumptions involved in the conflict, none if the formula is unsatisfiable.
+}
+
+
+// Literal implied by unit propagation, with the clause it was implied by (none for literals implied
+// by unit clauses or by the formula alone).
+pub type Implied = (Lit, Option<Vec<Lit>>);
+
+
+impl Searcher {
+    // Description:
+    //   Assigns the assumptions in order and propagates each of them, like the decisions of the
+    //   search, then backtracks to the ground level. No clauses are learnt.
+    //
+    // Output:
+    //   The literals true after the propagation except for the assumptions (in the order of the
+    //   trail, literals implied by the formula alone first), with reason clauses if 'explain'.
+    pub fn propagate_assumptions(&mut self, assumptions: &[Lit], explain: bool) -> Result<Vec<Implied>, Conflict> {
+        assert!(self.bt.is_ground_level());
+        let res = self.propagate_under(assumptions).map(|()| {
+            let ca = &self.bt.ca;
+            let assigns = &self.bt.assigns;
+            let mut assumed = vec![false; 2 * assigns.number_of_vars()];
+            for lit in assumptions.iter() {
+                assumed[lit.idx()] = true;
+            }
+
+            assigns.trail().iter()
+                .filter(|lit| !assumed[lit.idx()])
+                .map(|&lit| {
+                    let reason =
+                        if explain && !assigns.level(lit.var()).is_ground() {
+                            assigns.vardata(!lit).reason.map(|cr| ca.view(cr).lits().to_vec())
+                        } else {
+                            None
+                        };
+                    (lit, reason)
+                })
+                .collect()
+        });
+
+        self.cancel_until(GROUND_LEVEL);
+        res
+    }
+
+    fn propagate_under(&mut self, assumptions: &[Lit]) -> Result<(), Conflict> {
+        if let Some(confl) = self.bt.propagate() {
+            return Err(Conflict { clause: self.bt.ca.view(confl).lits().to_vec(), assumptions: Vec::new() });
+        }
+
+        for &lit in assumptions.iter() {
+            if self.bt.assigns.is_assigned_pos(lit) {
+                continue;
+            }
+
+            if self.bt.assigns.is_assigned_neg(lit) {
+                // The reason of the opposite literal is falsified by the assumption:
+                let clause =
+                    match self.bt.assigns.vardata(lit).reason {
+                        Some(cr) if !self.bt.assigns.level(lit.var()).is_ground() => self.bt.ca.view(cr).lits().to_vec(),
+                        _ => Vec::new(),
+                    };
+                let mut involved = self.involved_assumptions(&[lit]);
+                involved.push(lit);
+                return Err(Conflict { clause, assumptions: involved });
+            }
+
+            self.bt.push_decision(lit);
+            if let Some(confl) = self.bt.propagate() {
+                let clause = self.bt.ca.view(confl).lits().to_vec();
+                let involved = self.involved_assumptions(&clause);
+                return Err(Conflict { clause, assumptions: involved });
+            }
+        }
+        Ok(())
+    }
+
+    // Assumptions (decisions) that imply the negations of the false literals 'lits', in the order
+    // they were assumed.
+    fn involved_assumptions(&self, lits: &[Lit]) -> Vec<Lit> {
+        let ca = &self.bt.ca;
+        let assigns = &self.bt.assigns;
+        let mut seen = vec![false; assigns.number_of_vars()];
+        for lit in lits.iter() {
+            seen[lit.var().idx()] = true;
+        }
+
+        let mut involved = Vec::new();
+        for &lit in assigns.trail_above(GROUND_LEVEL).iter().rev() {
+            if seen[lit.var().idx()] {
+                match assigns.vardata(!lit).reason {
+                    Some(cr) => {
+                        for &l in &ca.view(cr).lits()[1..] {
+                            seen[l.var().idx()] = true;
+                        }
+                    }
+
+                    None => involved.push(lit),
+                }
+            }
+        }
+
+        involved.reverse();
+        involved
+    }
+}
@@ -1,4 +1,5 @@
 
+#[derive(Clone)]
 pub struct Random {
     seed: f64,
 }
@@ -24,4 +25,8 @@ impl Random {
     pub fn chance(&mut self, p: f64) -> bool {
         self.drand() < p
     }
+
+    pub fn seed(&self) -> f64 {
+        self.seed
+    }
 }
@@ -0,0 +1,248 @@
+use std::io;
+use std::collections::VecDeque;
+use super::{RestartPolicy, RestartStrategy};
+use super::checkpoint::{CheckpointReader, CheckpointWriter};
+
+
+// Average of the last 'size' values.
+#[derive(Clone)]
+struct Window {
+    values: VecDeque<u64>,
+    size: usize,
+    sum: u64,
+}
+
+impl Window {
+    fn new(size: usize) -> Window {
+        Window {
+            values: VecDeque::with_capacity(size),
+            size,
+            sum: 0,
+        }
+    }
+
+    fn push(&mut self, x: u64) {
+        if self.values.len() == self.size {
+            self.sum -= self.values.pop_front().unwrap();
+        }
+        self.values.push_back(x);
+        self.sum += x;
+    }
+
+    fn is_full(&self) -> bool {
+        self.values.len() == self.size
+    }
+
+    fn clear(&mut self) {
+        self.values.clear();
+        self.sum = 0;
+    }
+
+    fn value(&self) -> f64 {
+        self.sum as f64 / self.values.len().max(1) as f64
+    }
+}
+
+
+// Exponential moving average with smoothing factor 'alpha'. The first values are averaged with
+// larger factors, so that the average is not biased towards zero ('alpha = 0' gives the mean).
+#[derive(Clone)]
+struct Ema {
+    value: f64,
+    alpha: f64,
+    count: u64,
+}
+
+impl Ema {
+    fn new(alpha: f64) -> Ema {
+        Ema { value: 0.0, alpha, count: 0 }
+    }
+
+    fn push(&mut self, x: u64) {
+        self.count += 1;
+        let factor = self.alpha.max(1.0 / self.count as f64);
+        self.value += factor * (x as f64 - self.value);
+    }
+}
+
+
+#[derive(Clone)]
+enum Average {
+    Window(Window),
+    Ema(Ema),
+}
+
+impl Average {
+    fn new(policy: RestartPolicy, window: usize) -> Average {
+        match policy {
+            RestartPolicy::Ema => Average::Ema(Ema::new(1.0 / window as f64)),
+            _ => Average::Window(Window::new(window)),
+        }
+    }
+
+    fn push(&mut self, x: u64) {
+        match self {
+            Average::Window(w) => w.push(x),
+            Average::Ema(e) => e.push(x),
+        }
+    }
+
+    fn value(&self) -> f64 {
+        match self {
+            Average::Window(w) => w.value(),
+            Average::Ema(e) => e.value,
+        }
+    }
+
+    fn write_checkpoint<W: io::Write>(&self, w: &mut CheckpointWriter<W>) -> io::Result<()> {
+        match self {
+            Average::Window(window) => {
+                w.usize(window.values.len())?;
+                for &x in window.values.iter() {
+                    w.u64(x)?;
+                }
+                Ok(())
+            }
+
+            Average::Ema(ema) => ema.write_checkpoint(w),
+        }
+    }
+
+    fn read_checkpoint<R: io::Read>(&mut self, r: &mut CheckpointReader<R>) -> io::Result<()> {
+        match self {
+            Average::Window(window) => {
+                window.clear();
+                for _ in 0..r.usize()? {
+                    window.push(r.u64()?);
+                }
+                Ok(())
+            }
+
+            Average::Ema(ema) => ema.read_checkpoint(r),
+        }
+    }
+}
+
+impl Ema {
+    fn write_checkpoint<W: io::Write>(&self, w: &mut CheckpointWriter<W>) -> io::Result<()> {
+        w.f64(self.value)?;
+        w.u64(self.count)
+    }
+
+    fn read_checkpoint<R: io::Read>(&mut self, r: &mut CheckpointReader<R>) -> io::Result<()> {
+        self.value = r.f64()?;
+        self.count = r.u64()?;
+        Ok(())
+    }
+}
+
+
+// Decides when to restart. Static policies restart after a number of conflicts given by the Luby
+// or geometric sequence. Dynamic policies restart when the recently learnt clauses are worse (have
+// larger LBD) than the average, unless the trail is much larger than usual, which suggests that
+// the solver is approaching a model.
+#[derive(Clone)]
+pub struct RestartGuard {
+    settings: RestartStrategy,
+    restarts: u32,
+    started: bool,        // The current restart is started (an interrupted one continues).
+    conflicts_limit: u64, // Static policies: restart when the number of conflicts reaches this value.
+    since: u64,           // Number of conflicts of the last restart or blocking.
+    fast_lbd: Average,
+    slow_lbd: Ema,
+    trail: Average,
+}
+
+impl RestartGuard {
+    pub fn new(settings: RestartStrategy) -> Self {
+        RestartGuard {
+            settings,
+            restarts: 0,
+            started: false,
+            conflicts_limit: 0,
+            since: 0,
+            fast_lbd: Average::new(settings.policy, settings.lbd_window),
+            slow_lbd: Ema::new(if settings.policy == RestartPolicy::Ema { settings.slow_alpha } else { 0.0 }),
+            trail: Average::new(settings.policy, settings.trail_window),
+        }
+    }
+
+    // Called at the beginning of every restart, and when the search continues after an interruption.
+    pub fn start(&mut self, conflicts: u64) {
+        if !self.started {
+            self.started = true;
+            self.conflicts_limit = conflicts + self.settings.conflicts_to_go(self.restarts);
+            self.since = conflicts;
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.started = false;
+        self.restarts += 1;
+        if let Average::Window(ref mut w) = self.fast_lbd {
+            w.clear();
+        }
+    }
+
+    // Called for every conflict with the LBD of the learnt clause and the trail size at the conflict.
+    // Returns true if the restart was blocked.
+    pub fn conflict(&mut self, conflicts: u64, lbd: u32, trail: usize) -> bool {
+        if self.settings.policy == RestartPolicy::Static {
+            return false;
+        }
+
+        let mut blocked = false;
+        self.trail.push(trail as u64);
+        if let Some(factor) = self.settings.blocking {
+            if conflicts > self.settings.block_min_conflicts
+                && self.is_ready(conflicts)
+                && trail as f64 > factor * self.trail.value()
+            {
+                blocked = true;
+                self.since = conflicts;
+                if let Average::Window(ref mut w) = self.fast_lbd {
+                    w.clear();
+                }
+            }
+        }
+
+        self.fast_lbd.push(lbd as u64);
+        self.slow_lbd.push(lbd as u64);
+        blocked
+    }
+
+    fn is_ready(&self, conflicts: u64) -> bool {
+        match self.fast_lbd {
+            Average::Window(ref w) => w.is_full(),
+            Average::Ema(_) => conflicts - self.since >= self.settings.lbd_window as u64,
+        }
+    }
+
+    pub fn should_restart(&self, conflicts: u64) -> bool {
+        match self.settings.policy {
+            RestartPolicy::Static => conflicts >= self.conflicts_limit,
+            _ => self.is_ready(conflicts) && self.fast_lbd.value() > self.settings.lbd_margin * self.slow_lbd.value,
+        }
+    }
+
+    // The settings are not saved, they come from the resumed solver.
+    pub fn write_checkpoint<W: io::Write>(&self, w: &mut CheckpointWriter<W>) -> io::Result<()> {
+        w.u64(self.restarts as u64)?;
+        w.u64(self.started as u64)?;
+        w.u64(self.conflicts_limit)?;
+        w.u64(self.since)?;
+        self.fast_lbd.write_checkpoint(w)?;
+        self.slow_lbd.write_checkpoint(w)?;
+        self.trail.write_checkpoint(w)
+    }
+
+    pub fn read_checkpoint<R: io::Read>(&mut self, r: &mut CheckpointReader<R>) -> io::Result<()> {
+        self.restarts = r.u64()? as u32;
+        self.started = r.u64()? != 0;
+        self.conflicts_limit = r.u64()?;
+        self.since = r.u64()?;
+        self.fast_lbd.read_checkpoint(r)?;
+        self.slow_lbd.read_checkpoint(r)?;
+        self.trail.read_checkpoint(r)
+    }
+}
@@ -1,15 +1,21 @@
+use std::collections::BinaryHeap;
 use crate::sat::formula::{util::*, clause::*, LBool, Lit, Var, VarMap};
 use super::{util::*, SearchRes, SearchSettings, Searcher};
 use super::super::budget::Budget;
-use self::{elim_clauses::*, elim_queue::ElimOcc, subsumes::*, subsumption_queue::*};
+use self::{blocked::BlockedClauses, bva::VariableAddition, elim_clauses::*, elim_queue::ElimOcc, equivalence::ImplicationGraph, subsumes::*, subsumption_queue::*};
 use super::{backtrack::*, clause_db::*, decision_heuristic::*};
 
+mod blocked;
+mod bva;
 pub mod elim_clauses;
 mod elim_queue;
+mod equivalence;
+mod gates;
 pub mod subsumes;
 mod subsumption_queue;
 
 
+#[derive(Clone, Copy, Debug)]
 pub struct SimplificatorSettings {
     pub grow: usize, // Allow a variable elimination step to grow by a number of clauses (default to zero).
     pub clause_lim: i32, // Variables are not eliminated if it produces a resolvent with a length above this limit. -1 means no limit.
@@ -17,6 +23,14 @@ pub struct SimplificatorSettings {
     pub simp_garbage_frac: f64, // A different limit for when to issue a GC during simplification (Also see 'garbage_frac').
     pub use_asymm: bool,        // Shrink clauses by asymmetric branching.
     pub use_elim: bool,         // Perform variable elimination.
+    pub use_equiv: bool,        // Substitute equivalent literals (SCCs of the binary implication graph).
+    pub use_bce: bool,          // Perform blocked clause elimination.
+    pub use_cce: bool,          // Extend clauses by covered literal addition before checking if they are blocked.
+    pub use_abce: bool,         // Extend clauses by asymmetric literal addition before checking if they are blocked.
+    pub bce_steps: u64,         // Clause visits of blocked clause elimination allowed per literal of the formula.
+    pub use_bva: bool,          // Perform bounded variable addition.
+    pub bva_steps: u64,         // Clause visits of bounded variable addition allowed per literal of the formula.
+    pub use_gates: bool,        // Only produce resolvents with gate clauses if the eliminated variable is defined by a gate.
 }
 
 impl Default for SimplificatorSettings {
@@ -28,6 +42,14 @@ impl Default for SimplificatorSettings {
             simp_garbage_frac: 0.5,
             use_asymm: false,
             use_elim: true,
+            use_equiv: false,
+            use_bce: false,
+            use_cce: false,
+            use_abce: false,
+            bce_steps: 20,
+            use_bva: false,
+            bva_steps: 20,
+            use_gates: false,
         }
     }
 }
@@ -43,13 +65,18 @@ impl SimplificatorSettings {
 }
 
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 struct Stats {
     asymm_lits: u64,
     eliminated_vars: u64,
+    equiv_vars: u64,
+    blocked_clauses: u64,
+    added_vars: u64,
+    gates: u64,
 }
 
 
+#[derive(Clone)]
 pub struct Touched {
     touched: VarMap<bool>,
     n_touched: usize,
@@ -112,6 +139,7 @@ impl Touched {
 }
 
 
+#[derive(Clone)]
 pub struct Simplificator {
     settings: SimplificatorSettings,
     stats: Stats,
@@ -136,6 +164,13 @@ impl Simplificator {
         self.touched.init_var(v);
     }
 
+    pub fn set_frozen(&mut self, search: &Searcher, v: Var, frozen: bool) {
+        self.elo.var_status[&v].frozen = frozen;
+        if !frozen {
+            self.elo.elim.update_elim_heap(v, &self.elo.var_status, &search.bt.assigns);
+        }
+    }
+
     pub fn add_clause(&mut self, search: &mut Searcher, ps: &[Lit]) -> Result<(), ()> {
         //#ifndef NDEBUG
         for l in ps.iter() {
@@ -216,7 +251,43 @@ impl Simplificator {
         budget: &Budget,
         elimclauses: &mut ElimClauses,
     ) -> Result<(), ()> {
-        // Main simplification loop:
+        let before = search.ctx.db.stats.num_clauses;
+        let res = self.eliminate_all(search, budget, elimclauses);
+        if let Some(ref mut observer) = search.ctx.observer {
+            observer.simplified(before.saturating_sub(search.ctx.db.stats.num_clauses));
+        }
+        search.ctx.stats.eliminated_vars = self.stats.eliminated_vars;
+        search.ctx.stats.asymm_lits = self.stats.asymm_lits;
+        search.ctx.stats.equiv_vars = self.stats.equiv_vars;
+        search.ctx.stats.blocked_clauses = self.stats.blocked_clauses;
+        search.ctx.stats.added_vars = self.stats.added_vars;
+        search.ctx.stats.gates = self.stats.gates;
+        res
+    }
+
+    fn eliminate_all(
+        &mut self,
+        search: &mut Searcher,
+        budget: &Budget,
+        elimclauses: &mut ElimClauses,
+    ) -> Result<(), ()> {
+        if self.settings.use_equiv {
+            while self.substitute_equivalences(search, elimclauses)? {}
+            self.try_garbage_collect(search);
+        }
+
+        if self.settings.use_bva {
+            self.add_variables(search, budget)?;
+            self.try_garbage_collect(search);
+        }
+
+        if self.settings.use_bce {
+            self.eliminate_blocked(search, budget, elimclauses);
+            self.try_garbage_collect(search);
+        }
+
+        // Main simplification loop ('interrupted' is kept, since 'budget' polls 'terminate' rarely):
+        let mut interrupted = false;
         while !self.touched.is_empty() || self.subsumption_queue.assigns_left(&search.bt.assigns) > 0
             || self.elo.elim.len() > 0
         {
@@ -224,7 +295,7 @@ impl Simplificator {
             self.backward_subsumption_check(&mut search.bt, &mut search.ctx.db, budget, true)?;
 
             // Empty elim_heap and return immediately on user-interrupt:
-            if budget.interrupted() {
+            if interrupted || budget.interrupted() {
                 assert_eq!(self.subsumption_queue.assigns_left(&search.bt.assigns), 0);
                 assert!(self.subsumption_queue.is_empty());
                 assert!(self.touched.is_empty());
@@ -236,6 +307,7 @@ impl Simplificator {
             let mut cnt = 0;
             while let Some(var) = self.elo.elim.pop() {
                 if budget.interrupted() {
+                    interrupted = true;
                     break;
                 }
 
@@ -270,6 +342,10 @@ impl Simplificator {
             assert!(self.subsumption_queue.is_empty());
         }
 
+        if self.settings.use_gates {
+            trace!("BVE: {} variables eliminated, {} of them defined by gates", self.stats.eliminated_vars, self.stats.gates);
+        }
+
         Ok(())
     }
 
@@ -363,6 +439,13 @@ impl Simplificator {
             (pos, neg)
         };
 
+        let gate =
+            if self.settings.use_gates {
+                gates::find_gate(&search.bt.ca, v, &pos, &neg)
+            } else {
+                None
+            };
+
         // Check wether the increase in number of clauses stays within the allowed ('grow'). Moreover, no
         // clause must exceed the limit on the maximal clause size (if it is set):
         let resolvents = {
@@ -370,6 +453,10 @@ impl Simplificator {
             let mut resolvents = Vec::with_capacity(max_resolvents + 1);
             for &pr in pos.iter() {
                 for &nr in neg.iter() {
+                    if gate.as_ref().is_some_and(|gate| !gate.is_needed(pr, nr)) {
+                        continue;
+                    }
+
                     if let Some(resolvent) = merge(v, search.bt.ca.literals(pr), search.bt.ca.literals(nr)) {
                         let len = resolvent.len();
                         resolvents.push(resolvent);
@@ -386,6 +473,9 @@ impl Simplificator {
         self.elo.var_status[&v].eliminated = true;
         search.ctx.heur.set_decision_var(v, false);
         self.stats.eliminated_vars += 1;
+        if gate.is_some() {
+            self.stats.gates += 1;
+        }
 
         if pos.len() > neg.len() {
             for &cr in neg.iter() {
@@ -419,6 +509,228 @@ impl Simplificator {
         Ok(true)
     }
 
+    // Finds strongly connected components of the binary implication graph and replaces every
+    // literal of a component by the component representative. Returns true if something was
+    // substituted, so new binary clauses may have appeared.
+    fn substitute_equivalences(&mut self, search: &mut Searcher, elimclauses: &mut ElimClauses) -> Result<bool, ()> {
+        assert!(search.bt.is_ground_level());
+
+        let components = {
+            let graph = ImplicationGraph::build(&search.bt.ca, &search.bt.assigns, &mut self.elo);
+            graph.strongly_connected_components()
+        };
+
+        // Every component has its dual with all literals negated. Representative depends only on
+        // the set of variables, so both components agree on it. Frozen variables are preferred
+        // as representatives because they must stay in the formula:
+        let mut repr: VarMap<Lit> = VarMap::new();
+        let mut substituted = Vec::new();
+        for component in components.iter() {
+            let mut seen: VarMap<()> = VarMap::new();
+            for &lit in component.iter() {
+                if seen.insert(&lit.var(), ()).is_some() {
+                    // Both 'lit' and '¬lit' are in the same component:
+                    return Err(());
+                }
+            }
+
+            let r = *component.iter()
+                .min_by_key(|lit| (!self.elo.is_frozen(lit.var()), lit.var()))
+                .unwrap();
+
+            for &lit in component.iter() {
+                let v = lit.var();
+                if lit != r && !self.elo.is_frozen(v) && repr.get(&v).is_none() {
+                    repr.insert(&v, if lit.sign() { !r } else { r });
+                    substituted.push(v);
+                }
+            }
+        }
+
+        if substituted.is_empty() {
+            return Ok(false);
+        }
+
+        // Remove every clause mentioning a substituted variable and remember its substituted version:
+        let mut replaced = Vec::new();
+        for &v in substituted.iter() {
+            for &cr in self.elo.occurs.lookup(&search.bt.ca, v).clone().iter() {
+                if search.bt.ca.is_deleted(cr) {
+                    continue;
+                }
+
+                let lits: Vec<Lit> = search.bt.ca.literals(cr).iter()
+                    .map(|&lit| match repr.get(&lit.var()) {
+                        Some(&r) if lit.sign() => !r,
+                        Some(&r) => r,
+                        None => lit,
+                    })
+                    .collect();
+                replaced.push(lits);
+
+                self.elo.smudge_clause(&search.bt.assigns, search.bt.ca.literals(cr));
+                search.bt.lazy_detach(cr);
+                search.ctx.db.remove_clause(&mut search.bt.ca, cr);
+            }
+        }
+
+        for &v in substituted.iter() {
+            self.elo.var_status[&v].eliminated = true;
+            search.ctx.heur.set_decision_var(v, false);
+            elimclauses.mk_elim_equiv(v.pos_lit(), repr[&v]);
+            self.elo.occurs.clear_var(&v);
+            search.bt.try_clear_var(v);
+        }
+        self.stats.equiv_vars += substituted.len() as u64;
+        trace!("EQUIV: {} variables substituted", substituted.len());
+
+        for lits in replaced.iter() {
+            self.add_clause(search, lits.as_slice())?;
+        }
+
+        Ok(true)
+    }
+
+    // Removes blocked clauses (covered and asymmetric blocked clauses if enabled). Blocking
+    // literals of frozen variables are never used, since model extension may flip them.
+    fn eliminate_blocked(&mut self, search: &mut Searcher, budget: &Budget, elimclauses: &mut ElimClauses) {
+        assert!(search.bt.is_ground_level());
+
+        let vars: Vec<Var> = self.elo.var_status.iter()
+            .filter(|(v, st)| !st.eliminated && search.bt.assigns.is_undef(*v))
+            .map(|(v, _)| v)
+            .collect();
+
+        // Every clause is taken once, from the occurrence list of its first literal:
+        let mut candidates = Vec::new();
+        for &v in vars.iter() {
+            for &cr in self.elo.occurs.lookup(&search.bt.ca, v) {
+                if search.bt.ca.view(cr).prefix[0].var() == v {
+                    candidates.push(cr);
+                }
+            }
+        }
+
+        let step_limit = self.settings.bce_steps * search.ctx.db.stats.clauses_literals;
+        let mut steps = 0;
+        let mut removed = 0u64;
+        for &cr in candidates.iter() {
+            if budget.interrupted() || steps > step_limit {
+                break;
+            }
+
+            let witness = {
+                let mut bc = BlockedClauses {
+                    ca: &search.bt.ca,
+                    assigns: &search.bt.assigns,
+                    occurs: &mut self.elo.occurs,
+                    var_status: &self.elo.var_status,
+                    covered: self.settings.use_cce,
+                    asymmetric: self.settings.use_abce,
+                    steps: 0,
+                };
+                let witness = bc.try_eliminate(cr);
+                steps += bc.steps;
+                witness
+            };
+
+            if let Some(witness) = witness {
+                for (lits, lit) in witness.iter() {
+                    elimclauses.mk_elim_clause(lit.var(), lits);
+                }
+
+                removed += 1;
+                self.elo.smudge_clause(&search.bt.assigns, search.bt.ca.literals(cr));
+                search.bt.lazy_detach(cr);
+                search.ctx.db.remove_clause(&mut search.bt.ca, cr);
+            }
+        }
+
+        self.stats.blocked_clauses += removed;
+        trace!("BCE: {} clauses removed", removed);
+    }
+
+    // Bounded variable addition: replaces clause groups '(C_j ∨ l_i)' with '(l_i ∨ x)' and '(C_j ∨ ¬x)'
+    // for a fresh variable 'x' whenever it reduces the number of clauses. Literals are processed
+    // in order of decreasing number of occurrences.
+    fn add_variables(&mut self, search: &mut Searcher, budget: &Budget) -> Result<(), ()> {
+        assert!(search.bt.is_ground_level());
+
+        let mut queue = BinaryHeap::new();
+        for (v, st) in self.elo.var_status.iter() {
+            if !st.eliminated && search.bt.assigns.is_undef(v) {
+                for &lit in [v.pos_lit(), v.neg_lit()].iter() {
+                    queue.push((self.elo.elim.lit_occ(&lit), lit));
+                }
+            }
+        }
+
+        let step_limit = self.settings.bva_steps * search.ctx.db.stats.clauses_literals;
+        let mut steps = 0;
+        let mut added = 0u64;
+        while let Some((occ, lit)) = queue.pop() {
+            if budget.interrupted() || steps > step_limit {
+                break;
+            }
+
+            if self.elo.is_eliminated(lit.var()) || !search.bt.assigns.is_undef(lit.var()) || occ < 2 {
+                continue;
+            }
+
+            // Number of occurrences has changed since the literal was queued:
+            let actual = self.elo.elim.lit_occ(&lit);
+            if actual != occ {
+                queue.push((actual, lit));
+                continue;
+            }
+
+            let matching = {
+                let mut va = VariableAddition {
+                    ca: &search.bt.ca,
+                    assigns: &search.bt.assigns,
+                    occurs: &mut self.elo.occurs,
+                    elim: &self.elo.elim,
+                    steps: 0,
+                };
+                let matching = va.find_matching(lit);
+                steps += va.steps;
+                matching
+            };
+
+            if let Some(matching) = matching {
+                let x = search.new_var(None, true);
+                self.init_var(x);
+                added += 1;
+
+                for &cr in matching.replaced.iter() {
+                    if !search.bt.ca.is_deleted(cr) {
+                        self.elo.smudge_clause(&search.bt.assigns, search.bt.ca.literals(cr));
+                        search.bt.lazy_detach(cr);
+                        search.ctx.db.remove_clause(&mut search.bt.ca, cr);
+                    }
+                }
+
+                for &l in matching.lits.iter() {
+                    self.add_clause(search, &[l, x.pos_lit()])?;
+                }
+
+                for clause in matching.clauses.iter() {
+                    let mut lits = clause.clone();
+                    lits.push(x.neg_lit());
+                    self.add_clause(search, &lits[..])?;
+                }
+
+                for &l in matching.lits.iter() {
+                    queue.push((self.elo.elim.lit_occ(&l), l));
+                }
+            }
+        }
+
+        self.stats.added_vars += added;
+        trace!("BVA: {} variables added", added);
+        Ok(())
+    }
+
     // Backward subsumption + backward subsumption resolution
     fn backward_subsumption_check(
         &mut self,
@@ -525,9 +837,13 @@ impl Simplificator {
 
     fn try_garbage_collect(&mut self, search: &mut Searcher) {
         if search.bt.ca.check_garbage(self.settings.simp_garbage_frac) {
-            let mut gc = search.gc();
-            self.elo.occurs.gc(&mut gc);
-            self.subsumption_queue.gc(&mut gc);
+            let before = search.bt.ca.allocated_bytes();
+            {
+                let mut gc = search.gc();
+                self.elo.occurs.gc(&mut gc);
+                self.subsumption_queue.gc(&mut gc);
+            }
+            search.garbage_collected(before);
         }
     }
 
@@ -540,7 +856,9 @@ impl Simplificator {
         // Force full cleanup (this is safe and desirable since it only happens once):
         search.ctx.heur.rebuild_order_heap(&search.bt.assigns);
 
+        let before = search.bt.ca.allocated_bytes();
         search.gc();
+        search.garbage_collected(before);
     }
 
     pub fn on(search: &mut Searcher) {
@@ -0,0 +1,169 @@
+use crate::sat::formula::{assignment::Assignment, clause::*, Lit, VarMap};
+use super::elim_queue::{OccLists, VarStatus};
+
+
+// Extended clauses are not allowed to grow beyond this size:
+const MAX_EXTENDED_LEN: usize = 64;
+
+
+// Steps of a clause elimination in the order they have to be put to the reconstruction stack:
+// literal is flipped during model extension if its clause is not satisfied.
+pub type Witness = Vec<(Vec<Lit>, Lit)>;
+
+
+enum Resolution {
+    Blocked,
+    Covered(Vec<Lit>),
+    Nothing,
+}
+
+pub struct BlockedClauses<'a> {
+    pub ca: &'a ClauseAllocator,
+    pub assigns: &'a Assignment,
+    pub occurs: &'a mut OccLists,
+    pub var_status: &'a VarMap<VarStatus>,
+    pub covered: bool,    // Extend clauses by covered literal addition.
+    pub asymmetric: bool, // Extend clauses by asymmetric literal addition.
+    pub steps: u64,       // Number of clauses visited so far.
+}
+
+impl<'a> BlockedClauses<'a> {
+    // Checks if the clause is blocked (covered, asymmetric blocked) and can be removed from the
+    // formula. Clauses containing assigned literals are never removed.
+    pub fn try_eliminate(&mut self, cr: ClauseRef) -> Option<Witness> {
+        let mut ext = {
+            let c = self.ca.view(cr);
+            if c.is_deleted() || c.lits().iter().any(|lit| !self.assigns.is_undef(lit.var())) {
+                return None;
+            }
+            c.lits().to_vec()
+        };
+
+        let mut witness = Vec::new();
+        let mut asymm_head = 0;
+        while ext.len() <= MAX_EXTENDED_LEN {
+            if self.asymmetric && self.extend_asymmetric(cr, &mut ext, &mut asymm_head) {
+                // Extended clause is an asymmetric tautology:
+                return Some(witness);
+            }
+
+            let mut extended = false;
+            for i in 0..ext.len() {
+                let lit = ext[i];
+                if self.var_status[&lit.var()].frozen {
+                    continue;
+                }
+
+                match self.resolve_on(cr, &ext, lit) {
+                    Resolution::Blocked => {
+                        witness.push((ext, lit));
+                        return Some(witness);
+                    }
+
+                    Resolution::Covered(lits) => {
+                        witness.push((ext.clone(), lit));
+                        for &cov in lits.iter() {
+                            if ext.contains(&!cov) {
+                                // Extended clause became a tautology:
+                                return Some(witness);
+                            }
+                            ext.push(cov);
+                        }
+                        extended = true;
+                        break;
+                    }
+
+                    Resolution::Nothing => {}
+                }
+            }
+
+            if !extended {
+                break;
+            }
+        }
+
+        None
+    }
+
+    // Looks at all resolvents of the extended clause on 'lit'. If all of them are tautologies,
+    // the clause is blocked. Otherwise, literals common to all non-tautological resolution
+    // partners are covered and can be added to the clause.
+    fn resolve_on(&mut self, cr: ClauseRef, ext: &[Lit], lit: Lit) -> Resolution {
+        let assigns = self.assigns;
+        let mut common: Option<Vec<Lit>> = None;
+        let occs = self.occurs.lookup(self.ca, lit.var());
+        self.steps += occs.len() as u64;
+        for &dr in occs {
+            let d = self.ca.view(dr);
+            if dr == cr || d.is_deleted() || !d.lits().contains(&!lit)
+                || d.lits().iter().any(|&k| assigns.is_assigned_pos(k))
+            {
+                continue;
+            }
+
+            let tautology = d.lits().iter().any(|&k| k != !lit && ext.contains(&!k));
+            if tautology {
+                continue;
+            }
+
+            let rest = d.lits().iter().filter(|&&k| {
+                k != !lit && !ext.contains(&k) && assigns.is_undef(k.var())
+            });
+
+            let common = match common {
+                None => common.insert(rest.cloned().collect()),
+                Some(ref mut common) => {
+                    common.retain(|k| d.lits().contains(k));
+                    common
+                }
+            };
+
+            if !self.covered || common.is_empty() {
+                return Resolution::Nothing;
+            }
+        }
+
+        match common {
+            None => Resolution::Blocked,
+            Some(lits) => Resolution::Covered(lits),
+        }
+    }
+
+    // Adds '¬m' to the extended clause for every clause '(l1 ∨ .. ∨ lk ∨ m)' with all 'li' in it.
+    // Literals of the extended clause starting from 'head' are used as a work queue, since every
+    // such clause contains the last added 'li'. Returns true if the extended clause became an
+    // asymmetric tautology.
+    fn extend_asymmetric(&mut self, cr: ClauseRef, ext: &mut Vec<Lit>, head: &mut usize) -> bool {
+        let assigns = self.assigns;
+        while *head < ext.len() && ext.len() <= MAX_EXTENDED_LEN {
+            let lit = ext[*head];
+            *head += 1;
+
+            let occs = self.occurs.lookup(self.ca, lit.var());
+            self.steps += occs.len() as u64;
+            for &dr in occs {
+                let d = self.ca.view(dr);
+                if dr == cr || d.is_deleted() || !d.lits().contains(&lit)
+                    || d.lits().iter().any(|&k| assigns.is_assigned_pos(k))
+                {
+                    continue;
+                }
+
+                let m = {
+                    let mut missing = d.lits().iter()
+                        .filter(|&&k| !ext.contains(&k) && assigns.is_undef(k.var()));
+
+                    match (missing.next(), missing.next()) {
+                        (None, _) => { return true; }
+                        (Some(&m), None) if !ext.contains(&!m) => m,
+                        _ => { continue; }
+                    }
+                };
+
+                ext.push(!m);
+            }
+        }
+
+        false
+    }
+}
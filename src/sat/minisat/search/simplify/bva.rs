@@ -0,0 +1,145 @@
+use crate::sat::formula::{assignment::Assignment, clause::*, Lit};
+use super::elim_queue::{ElimQueue, OccLists};
+
+
+// Clause group '(C_j ∨ l_i)' for all 'i' and 'j', which can be replaced by clauses '(l_i ∨ x)' and
+// '(C_j ∨ ¬x)' with a fresh variable 'x'.
+pub struct Matching {
+    pub lits: Vec<Lit>,           // 'l_i'
+    pub clauses: Vec<Vec<Lit>>,   // 'C_j'
+    pub replaced: Vec<ClauseRef>, // All the '(C_j ∨ l_i)'
+}
+
+
+pub struct VariableAddition<'a> {
+    pub ca: &'a ClauseAllocator,
+    pub assigns: &'a Assignment,
+    pub occurs: &'a mut OccLists,
+    pub elim: &'a ElimQueue,
+    pub steps: u64, // Number of clauses visited so far.
+}
+
+impl<'a> VariableAddition<'a> {
+    // Greedily grows the set of matched literals, starting with 'lit' and all clauses containing
+    // it, while the number of clauses saved by the replacement increases.
+    pub fn find_matching(&mut self, lit: Lit) -> Option<Matching> {
+        let ca = self.ca;
+        let mut lits = vec![lit];
+        let mut clauses = self.clauses_with(lit);
+
+        loop {
+            // Pairs '(l, C)' such that '(C \ {lit}) ∪ {l}' is in the formula as well:
+            let mut pairs = Vec::new();
+            for &cr in clauses.iter() {
+                let c = ca.view(cr);
+                let lmin = {
+                    let elim = self.elim;
+                    *c.lits().iter()
+                        .filter(|&&k| k != lit)
+                        .min_by_key(|&&k| elim.lit_occ(&k))
+                        .unwrap()
+                };
+
+                for dr in self.clauses_with(lmin) {
+                    let d = ca.view(dr);
+                    if dr == cr || d.len() != c.len() || d.lits().contains(&lit) {
+                        continue;
+                    }
+
+                    let mut diff = d.lits().iter().filter(|k| !c.lits().contains(k));
+                    if let (Some(&other), None) = (diff.next(), diff.next()) {
+                        if other != !lit && !lits.contains(&other) {
+                            pairs.push((other, cr));
+                        }
+                    }
+                }
+            }
+
+            // Pick the literal matching the most clauses:
+            pairs.sort_by_key(|&(l, _)| l);
+            let best = {
+                let mut best: Option<(Lit, Vec<ClauseRef>)> = None;
+                let mut i = 0;
+                while i < pairs.len() {
+                    let l = pairs[i].0;
+                    let mut j = i;
+                    let mut group: Vec<ClauseRef> = Vec::new();
+                    while j < pairs.len() && pairs[j].0 == l {
+                        if !group.contains(&pairs[j].1) {
+                            group.push(pairs[j].1);
+                        }
+                        j += 1;
+                    }
+
+                    match best {
+                        Some((_, ref best_group)) if best_group.len() >= group.len() => {}
+                        _ => { best = Some((l, group)); }
+                    }
+                    i = j;
+                }
+                best
+            };
+
+            match best {
+                Some((l, group)) if reduction(lits.len() + 1, group.len()) > reduction(lits.len(), clauses.len()) => {
+                    lits.push(l);
+                    clauses = group;
+                }
+
+                _ => { break; }
+            }
+        }
+
+        if lits.len() < 2 || reduction(lits.len(), clauses.len()) <= 0 {
+            return None;
+        }
+
+        let mut matching = Matching {
+            lits: lits.clone(),
+            clauses: Vec::with_capacity(clauses.len()),
+            replaced: Vec::with_capacity(lits.len() * clauses.len()),
+        };
+
+        for &cr in clauses.iter() {
+            let rest: Vec<Lit> = ca.literals(cr).iter().cloned().filter(|&k| k != lit).collect();
+            matching.replaced.push(cr);
+
+            for &l in lits[1..].iter() {
+                let found = self.clauses_with(l).into_iter().find(|&dr| {
+                    let d = ca.view(dr);
+                    d.len() == rest.len() + 1 && rest.iter().all(|k| d.lits().contains(k))
+                });
+
+                match found {
+                    Some(dr) => { matching.replaced.push(dr); }
+                    None => { return None; }
+                }
+            }
+
+            matching.clauses.push(rest);
+        }
+
+        Some(matching)
+    }
+
+    // Clauses with literal 'lit' and without assigned literals:
+    fn clauses_with(&mut self, lit: Lit) -> Vec<ClauseRef> {
+        let ca = self.ca;
+        let assigns = self.assigns;
+        let occs = self.occurs.lookup(ca, lit.var());
+        self.steps += occs.len() as u64;
+        occs.iter()
+            .cloned()
+            .filter(|&cr| {
+                let c = ca.view(cr);
+                !c.is_deleted() && c.lits().contains(&lit) && c.lits().iter().all(|k| assigns.is_undef(k.var()))
+            })
+            .collect()
+    }
+}
+
+
+// Number of clauses saved by replacing 'lits * clauses' clauses with 'lits + clauses' ones:
+fn reduction(lits: usize, clauses: usize) -> isize {
+    (lits * clauses) as isize - (lits + clauses) as isize
+}
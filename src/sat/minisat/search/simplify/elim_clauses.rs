@@ -1,7 +1,9 @@
-use std::mem;
+use std::{io, mem};
 use crate::sat::formula::{util, Lit, Var, VarMap};
+use super::super::checkpoint::{CheckpointReader, CheckpointWriter};
 
 
+#[derive(Clone)]
 pub struct ElimClauses {
     extend_model: bool,
     literals: Vec<Lit>,
@@ -40,6 +42,12 @@ impl ElimClauses {
         }
     }
 
+    // Remembers that 'x' is equivalent to 'repr', so the value of 'x' is restored from 'repr':
+    pub fn mk_elim_equiv(&mut self, x: Lit, repr: Lit) {
+        self.mk_elim_clause(x.var(), &[x, !repr]);
+        self.mk_elim_clause(x.var(), &[!x, repr]);
+    }
+
     pub fn extend_model(&self, model: &mut VarMap<bool>) {
         if !self.extend_model {
             return;
@@ -62,6 +70,28 @@ impl ElimClauses {
         }
     }
 
+    pub fn write_checkpoint<W: io::Write>(&self, w: &mut CheckpointWriter<W>) -> io::Result<()> {
+        w.lits(&self.literals)?;
+        w.usize(self.sizes.len())?;
+        for &size in self.sizes.iter() {
+            w.usize(size)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_checkpoint<R: io::Read>(&mut self, r: &mut CheckpointReader<R>, vars: usize) -> io::Result<()> {
+        self.literals = r.lits(vars)?;
+        self.sizes.clear();
+        for _ in 0..r.usize()? {
+            let size = r.usize()?;
+            if size > self.literals.len() || self.sizes.last().is_some_and(|&last| size <= last) {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid checkpoint: eliminated clauses"));
+            }
+            self.sizes.push(size);
+        }
+        Ok(())
+    }
+
     pub fn log_size(&self) {
         let sz = self.literals.len() + self.sizes.len();
         if sz > 0 {
@@ -3,13 +3,14 @@ use crate::sat::formula::assignment::Assignment;
 use crate::sat::formula::clause::*;
 
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct VarStatus {
     pub frozen: bool,
     pub eliminated: bool,
 }
 
 
+#[derive(Clone)]
 pub struct ElimQueue {
     heap: VarHeap,
     n_occ: LitMap<isize>,
@@ -61,6 +62,10 @@ impl ElimQueue {
         self.heap.clear();
     }
 
+    pub fn lit_occ(&self, lit: &Lit) -> isize {
+        self.n_occ[lit]
+    }
+
     pub fn bump_lit_occ(&mut self, lit: &Lit, delta: isize) {
         self.n_occ[lit] += delta;
 
@@ -76,12 +81,13 @@ impl ElimQueue {
 }
 
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct OccLine {
     occs: Vec<ClauseRef>,
     dirty: bool,
 }
 
+#[derive(Clone)]
 pub struct OccLists {
     occs: VarMap<OccLine>,
 }
@@ -151,6 +157,7 @@ impl OccLists {
 }
 
 
+#[derive(Clone)]
 pub struct ElimOcc {
     pub occurs: OccLists,
     pub elim: ElimQueue,
@@ -0,0 +1,120 @@
+use std::cmp;
+use crate::sat::formula::{assignment::Assignment, clause::*, Lit, LitMap, Var};
+use super::elim_queue::ElimOcc;
+
+
+// Binary implication graph of the problem clauses: every binary clause '(a ∨ b)' with both
+// literals unassigned contributes the edges '¬a → b' and '¬b → a'.
+pub struct ImplicationGraph {
+    nodes: Vec<Lit>,
+    edges: LitMap<Vec<Lit>>,
+}
+
+impl ImplicationGraph {
+    pub fn build(ca: &ClauseAllocator, assigns: &Assignment, elo: &mut ElimOcc) -> Self {
+        let vars: Vec<Var> = elo.var_status.iter()
+            .filter(|(v, st)| !st.eliminated && assigns.is_undef(*v))
+            .map(|(v, _)| v)
+            .collect();
+
+        let mut graph = ImplicationGraph {
+            nodes: Vec::with_capacity(2 * vars.len()),
+            edges: LitMap::new(),
+        };
+
+        for &v in vars.iter() {
+            for &lit in [v.pos_lit(), v.neg_lit()].iter() {
+                graph.nodes.push(lit);
+                graph.edges.insert(&lit, Vec::new());
+            }
+        }
+
+        for &v in vars.iter() {
+            for &cr in elo.occurs.lookup(ca, v) {
+                let c = ca.view(cr);
+                if c.len() != 2 {
+                    continue;
+                }
+
+                let [a, b] = c.prefix;
+                // Every binary clause is present in two occurrence lists, take it only once:
+                if cmp::min(a.var(), b.var()) != v || !assigns.is_undef(a.var()) || !assigns.is_undef(b.var()) {
+                    continue;
+                }
+
+                graph.edges[&!a].push(b);
+                graph.edges[&!b].push(a);
+            }
+        }
+
+        graph
+    }
+
+    // Tarjan's algorithm (iterative version). Only non-trivial components are returned.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<Lit>> {
+        let mut index: LitMap<usize> = LitMap::new();
+        let mut lowlink: LitMap<usize> = LitMap::new();
+        let mut on_stack: LitMap<()> = LitMap::new();
+        let mut stack = Vec::new();
+        let mut calls: Vec<(Lit, usize)> = Vec::new();
+        let mut counter = 0;
+        let mut components = Vec::new();
+
+        for &root in self.nodes.iter() {
+            if index.get(&root).is_some() {
+                continue;
+            }
+
+            index.insert(&root, counter);
+            lowlink.insert(&root, counter);
+            counter += 1;
+            stack.push(root);
+            on_stack.insert(&root, ());
+            calls.push((root, 0));
+
+            while let Some(&mut (v, ref mut next)) = calls.last_mut() {
+                if let Some(&w) = self.edges[&v].get(*next) {
+                    *next += 1;
+                    match index.get(&w) {
+                        None => {
+                            index.insert(&w, counter);
+                            lowlink.insert(&w, counter);
+                            counter += 1;
+                            stack.push(w);
+                            on_stack.insert(&w, ());
+                            calls.push((w, 0));
+                        }
+
+                        Some(&wi) if on_stack.get(&w).is_some() => {
+                            lowlink[&v] = cmp::min(lowlink[&v], wi);
+                        }
+
+                        Some(_) => {}
+                    }
+                } else {
+                    calls.pop();
+                    if let Some(&(u, _)) = calls.last() {
+                        lowlink[&u] = cmp::min(lowlink[&u], lowlink[&v]);
+                    }
+
+                    if lowlink[&v] == index[&v] {
+                        let mut component = Vec::new();
+                        while let Some(w) = stack.pop() {
+                            on_stack.remove(&w);
+                            component.push(w);
+                            if w == v {
+                                break;
+                            }
+                        }
+
+                        if component.len() > 1 {
+                            components.push(component);
+                        }
+                    }
+                }
+            }
+        }
+
+        components
+    }
+}
@@ -0,0 +1,166 @@
+use crate::sat::formula::{clause::*, Lit, Var};
+
+
+// XOR gates are only looked for among clauses up to this size ('2^(len - 1)' clauses in total):
+const MAX_XOR_LEN: usize = 5;
+
+
+// Clauses defining a variable 'x' as a function of other variables, split by the polarity of 'x'.
+// Resolvents of two gate clauses are tautologies, and resolvents of two non-gate clauses are implied
+// by the resolvents of gate clauses with non-gate ones, so only the latter have to be produced.
+pub struct Gate {
+    pub pos: Vec<ClauseRef>, // Gate clauses containing 'x'.
+    pub neg: Vec<ClauseRef>, // Gate clauses containing '¬x'.
+}
+
+impl Gate {
+    fn flip(self) -> Gate {
+        Gate { pos: self.neg, neg: self.pos }
+    }
+
+    // Checks if the resolvent of two clauses is needed.
+    pub fn is_needed(&self, pr: ClauseRef, nr: ClauseRef) -> bool {
+        self.pos.contains(&pr) != self.neg.contains(&nr)
+    }
+}
+
+
+// Looks for an AND (equivalence, OR), ITE or XOR gate defining 'v'. 'pos' and 'neg' are the clauses
+// containing 'v' positively and negatively.
+pub fn find_gate(ca: &ClauseAllocator, v: Var, pos: &[ClauseRef], neg: &[ClauseRef]) -> Option<Gate> {
+    find_and(ca, v.pos_lit(), pos, neg)
+        .or_else(|| find_and(ca, v.neg_lit(), neg, pos).map(Gate::flip))
+        .or_else(|| find_ite(ca, v.pos_lit(), pos, neg))
+        .or_else(|| find_xor(ca, v, pos, neg))
+}
+
+
+// 'x = ¬k1 ∧ .. ∧ ¬kn': clause '(x ∨ k1 ∨ .. ∨ kn)' and all binary clauses '(¬x ∨ ¬ki)'.
+fn find_and(ca: &ClauseAllocator, x: Lit, xs: &[ClauseRef], not_xs: &[ClauseRef]) -> Option<Gate> {
+    let mut implied: Vec<(Lit, ClauseRef)> = not_xs.iter()
+        .filter_map(|&cr| {
+            match ca.literals(cr) {
+                &[a, b] => Some((if a == !x { b } else { a }, cr)),
+                _ => None,
+            }
+        })
+        .collect();
+
+    if implied.is_empty() {
+        return None;
+    }
+    implied.sort_by_key(|&(lit, _)| lit);
+
+    'clauses: for &cr in xs.iter() {
+        let mut binaries = Vec::new();
+        for &k in ca.literals(cr).iter().filter(|&&k| k != x) {
+            match implied.binary_search_by_key(&!k, |&(lit, _)| lit) {
+                Ok(i) => { binaries.push(implied[i].1); }
+                Err(_) => { continue 'clauses; }
+            }
+        }
+
+        return Some(Gate { pos: vec![cr], neg: binaries });
+    }
+
+    None
+}
+
+
+// 'x = c ? t : e': clauses '(¬x ∨ ¬c ∨ t)', '(¬x ∨ c ∨ e)', '(x ∨ ¬c ∨ ¬t)' and '(x ∨ c ∨ ¬e)'.
+fn find_ite(ca: &ClauseAllocator, x: Lit, xs: &[ClauseRef], not_xs: &[ClauseRef]) -> Option<Gate> {
+    let ternary = |cls: &[ClauseRef], l: Lit| -> Vec<(Lit, Lit, ClauseRef)> {
+        cls.iter()
+            .filter_map(|&cr| {
+                let mut rest = ca.literals(cr).iter().cloned().filter(|&k| k != l);
+                match (rest.next(), rest.next(), rest.next()) {
+                    (Some(a), Some(b), None) => Some((a, b, cr)),
+                    _ => None,
+                }
+            })
+            .collect()
+    };
+
+    let find = |cls: &[(Lit, Lit, ClauseRef)], a: Lit, b: Lit| {
+        cls.iter().find(|&&(p, q, _)| (p, q) == (a, b) || (p, q) == (b, a)).map(|&(_, _, cr)| cr)
+    };
+
+    let pos = ternary(xs, x);
+    let neg = ternary(not_xs, !x);
+    for &(a, b, then_neg) in neg.iter() {
+        for &(not_c, t) in [(a, b), (b, a)].iter() {
+            let then_pos = match find(&pos, not_c, !t) {
+                Some(cr) => cr,
+                None => { continue; }
+            };
+
+            for &(p, q, else_neg) in neg.iter() {
+                let e = if p == !not_c { q } else if q == !not_c { p } else { continue };
+                if let Some(else_pos) = find(&pos, !not_c, !e) {
+                    return Some(Gate { pos: vec![then_pos, else_pos], neg: vec![then_neg, else_neg] });
+                }
+            }
+        }
+    }
+
+    None
+}
+
+
+// 'x = k1 ⊕ .. ⊕ kn ⊕ const': all clauses over the same variables with the same parity of
+// negative literals.
+fn find_xor(ca: &ClauseAllocator, v: Var, pos: &[ClauseRef], neg: &[ClauseRef]) -> Option<Gate> {
+    let mut candidates: Vec<(Vec<Var>, ClauseRef)> = pos.iter().chain(neg.iter())
+        .filter_map(|&cr| {
+            let lits = ca.literals(cr);
+            if 3 <= lits.len() && lits.len() <= MAX_XOR_LEN {
+                let mut vars: Vec<Var> = lits.iter().map(|lit| lit.var()).collect();
+                vars.sort();
+                Some((vars, cr))
+            } else {
+                None
+            }
+        })
+        .collect();
+    candidates.sort_by(|x, y| x.0.cmp(&y.0));
+
+    // Negative literals of the clause as a bit mask over the sorted variables:
+    let mask_of = |cr: ClauseRef, vars: &[Var]| {
+        ca.literals(cr).iter()
+            .filter(|lit| lit.sign())
+            .fold(0usize, |mask, lit| mask | (1 << vars.binary_search(&lit.var()).unwrap()))
+    };
+
+    let mut i = 0;
+    while i < candidates.len() {
+        let vars = &candidates[i].0;
+        let end = i + candidates[i..].iter().take_while(|x| x.0 == *vars).count();
+        let group = &candidates[i..end];
+        i = end;
+
+        let required = 1 << (vars.len() - 1);
+        if group.len() < required {
+            continue;
+        }
+
+        for parity in 0..2 {
+            let mut found: Vec<Option<ClauseRef>> = vec![None; 2 * required];
+            for &(_, cr) in group.iter() {
+                let mask = mask_of(cr, vars);
+                if mask.count_ones() % 2 == parity {
+                    found[mask] = Some(cr);
+                }
+            }
+
+            let found: Vec<ClauseRef> = found.into_iter().flatten().collect();
+            if found.len() == required {
+                let (gate_pos, gate_neg) = found.into_iter()
+                    .partition(|&cr| ca.literals(cr).contains(&v.pos_lit()));
+
+                return Some(Gate { pos: gate_pos, neg: gate_neg });
+            }
+        }
+    }
+
+    None
+}
@@ -2,11 +2,13 @@ use std::collections::vec_deque;
 use crate::sat::formula::{assignment::*, clause::*, Lit};
 
 
+#[derive(Clone)]
 pub struct SubsumptionQueue {
     subsumption_queue: vec_deque::VecDeque<ClauseRef>,
     bwdsub_assigns: usize,
 }
 
+#[derive(Clone)]
 pub enum SubsumptionJob {
     Clause(ClauseRef),
     Assign(Lit),
@@ -71,3 +71,46 @@ pub fn asymmetric_branching(bt: &mut BacktrackableFormula, heur: &mut DecisionHe
     bt.assigns.backtrack_to(GROUND_LEVEL);
     confl.map(|_| l)
 }
+
+// Number of distinct decision levels among the literals of the clause.
+pub fn literal_block_distance(assigns: &Assignment, c: &[Lit]) -> u32 {
+    let mut levels: Vec<DecisionLevel> = c.iter().map(|&lit| assigns.vardata(lit).level).collect();
+    levels.sort();
+    levels.dedup();
+    levels.len() as u32
+}
+
+// Assigns the negations of the clause literals one by one. If a conflict arises, or some literal
+// becomes implied, the rest of the clause is redundant; false literals are redundant as well.
+// Returns the shortened clause, if any. The clause must not be satisfied or attached.
+pub fn vivify_clause(bt: &mut BacktrackableFormula, cr: ClauseRef) -> Option<Vec<Lit>> {
+    assert!(bt.is_ground_level());
+
+    let lits = bt.ca.view(cr).lits().to_vec();
+    let mut kept = Vec::with_capacity(lits.len());
+
+    bt.assigns.new_decision_level();
+    for &lit in lits.iter() {
+        match bt.assigns.of_lit(lit) {
+            LBool::True => {
+                kept.push(lit);
+                break;
+            }
+            LBool::Undef => {
+                kept.push(lit);
+                bt.assigns.assign_lit(!lit, None);
+                if bt.propagate().is_some() {
+                    break;
+                }
+            }
+            LBool::False => {}
+        }
+    }
+    bt.assigns.backtrack_to(GROUND_LEVEL);
+
+    if kept.len() < lits.len() {
+        Some(kept)
+    } else {
+        None
+    }
+}
@@ -9,13 +9,14 @@ struct Watcher {
 }
 
 
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 struct WatchesLine {
     watchers: Vec<Watcher>,
     dirty: bool,
 }
 
 
+#[derive(Clone)]
 pub struct Watches {
     watches: LitVec<WatchesLine>,
     pub propagations: u64,
@@ -139,7 +140,31 @@ impl Watches {
 
                         return Some(cw.cref);
                     } else {
-                        assigns.assign_lit(cw.blocker, Some(cw.cref));
+                        let p_level = assigns.level(p.var());
+                        if p_level == assigns.current_level() {
+                            assigns.assign_lit(cw.blocker, Some(cw.cref));
+                        } else {
+                            // Out of order propagation (chronological backtracking): the literal is
+                            // implied at the highest level of the rest, which is watched instead of 'p'.
+                            let lits = clause.lits_mut();
+                            let mut max_i = 1;
+                            let mut max_level = p_level;
+                            for (i, lit) in lits.iter().enumerate().skip(2) {
+                                let level = assigns.level(lit.var());
+                                if level > max_level {
+                                    max_i = i;
+                                    max_level = level;
+                                }
+                            }
+
+                            if max_i != 1 {
+                                lits.swap(1, max_i);
+                                tail = tail.offset(-1);
+                                self.watches[!lits[1]].watchers.push(cw);
+                            }
+
+                            assigns.assign_lit_at(cw.blocker, Some(cw.cref), max_level);
+                        }
                     }
                 }
 
@@ -0,0 +1,178 @@
+use crate::sat::{IncrementalRes, IncrementalSolver};
+use crate::sat::formula::{Idx, Lit};
+use crate::sat::minisat::budget::Budget;
+
+
+// Group of the hard clauses in the occurrence lists.
+const HARD: usize = usize::MAX;
+
+
+pub enum MusRes {
+    Mus(Vec<usize>), // Indices of the groups, in increasing order.
+    Sat,
+    Interrupted,
+}
+
+
+// Description:
+//   Computes a minimal unsatisfiable subset of the groups of clauses, i.e. an unsatisfiable set of
+//   groups that is satisfiable without any one of them, together with the hard clauses. Every group
+//   is enabled by a selector literal added to its clauses. Deletion-based: the candidate groups are
+//   tested one by one by solving without them. An unsatisfiable solve drops every group outside of
+//   its core (clause-set refinement), a satisfiable one marks the group as necessary, and so are the
+//   groups its model can be rotated to (see 'Rotation').
+//
+// Output:
+//   The MUS, empty if the hard clauses are unsatisfiable, unless the groups are satisfiable or the
+//   budget runs out.
+pub fn mus<S: IncrementalSolver>(solver: &mut S, budget: &Budget, hard: &[Vec<Lit>], groups: &[Vec<Vec<Lit>>]) -> MusRes {
+    for clause in hard.iter() {
+        solver.add_clause(clause);
+    }
+
+    let selectors: Vec<Lit> =
+        groups.iter()
+            .map(|group| {
+                let selector = solver.new_var(None, true).pos_lit();
+                for clause in group.iter() {
+                    let mut lits = clause.clone();
+                    lits.push(!selector);
+                    solver.add_clause(&lits);
+                }
+                selector
+            })
+            .collect();
+
+    let mut group_of = vec![None; solver.n_vars()];
+    for (g, selector) in selectors.iter().enumerate() {
+        group_of[selector.var().idx()] = Some(g);
+    }
+    let core_groups = |core: Vec<Lit>| -> Vec<usize> {
+        let mut core: Vec<usize> = core.iter().filter_map(|lit| group_of[lit.var().idx()]).collect();
+        core.sort_unstable();
+        core.dedup();
+        core
+    };
+
+    let mut candidates =
+        match solver.solve_incremental(budget, &selectors) {
+            IncrementalRes::Sat(_) => return MusRes::Sat,
+            IncrementalRes::UnSat(core) => core_groups(core),
+            IncrementalRes::Interrupted(_) => return MusRes::Interrupted,
+        };
+
+    let rotation = Rotation::new(solver.n_vars(), hard, groups);
+    let mut necessary = vec![false; groups.len()];
+    while let Some(&g) = candidates.iter().find(|&&g| !necessary[g]) {
+        let assumptions: Vec<Lit> = candidates.iter().filter(|&&c| c != g).map(|&c| selectors[c]).collect();
+        match solver.solve_incremental(budget, &assumptions) {
+            IncrementalRes::Sat(model) => {
+                let mut value = vec![false; solver.n_vars()];
+                for lit in model {
+                    value[lit.var().idx()] = !lit.sign();
+                }
+
+                let mut active = vec![false; groups.len()];
+                for &c in candidates.iter() {
+                    active[c] = true;
+                }
+
+                necessary[g] = true;
+                rotation.rotate(g, value, &active, &mut necessary);
+            }
+
+            IncrementalRes::UnSat(core) => {
+                let refined = core_groups(core);
+                for &c in candidates.iter() {
+                    if refined.binary_search(&c).is_err() {
+                        solver.add_clause(&[!selectors[c]]);
+                    }
+                }
+                candidates = refined;
+            }
+
+            IncrementalRes::Interrupted(_) => return MusRes::Interrupted,
+        }
+    }
+
+    MusRes::Mus(candidates)
+}
+
+
+// Model rotation: a model that falsifies only (clauses of) group 'g' of the candidates proves it
+// necessary. If flipping one variable satisfies 'g' and falsifies exactly one other group, the new
+// model proves that group necessary in turn, without calling the solver.
+struct Rotation<'c> {
+    hard: &'c [Vec<Lit>],
+    groups: &'c [Vec<Vec<Lit>>],
+    occurs: Vec<Vec<(usize, usize)>>, // Clauses (group and index) by variable.
+}
+
+impl<'c> Rotation<'c> {
+    fn new(n_vars: usize, hard: &'c [Vec<Lit>], groups: &'c [Vec<Vec<Lit>>]) -> Self {
+        let mut occurs = vec![Vec::new(); n_vars];
+        let mut add = |index, clause: &[Lit]| {
+            for lit in clause.iter() {
+                let occ: &mut Vec<(usize, usize)> = &mut occurs[lit.var().idx()];
+                if occ.last() != Some(&index) {
+                    occ.push(index);
+                }
+            }
+        };
+        for (i, clause) in hard.iter().enumerate() {
+            add((HARD, i), clause);
+        }
+        for (g, group) in groups.iter().enumerate() {
+            for (i, clause) in group.iter().enumerate() {
+                add((g, i), clause);
+            }
+        }
+
+        Rotation { hard, groups, occurs }
+    }
+
+    fn clause(&self, (g, i): (usize, usize)) -> &[Lit] {
+        if g == HARD { &self.hard[i] } else { &self.groups[g][i] }
+    }
+
+    // Description:
+    //   Rotates the model 'value' of group 'g', which satisfies the hard clauses and the 'active'
+    //   groups except for 'g', marking the groups it reaches as necessary.
+    fn rotate(&self, g: usize, value: Vec<bool>, active: &[bool], necessary: &mut [bool]) {
+        let falsified = |value: &[bool], clause: &[Lit]| clause.iter().all(|lit| value[lit.var().idx()] == lit.sign());
+
+        let mut stack = vec![(g, value)];
+        while let Some((g, mut value)) = stack.pop() {
+            let unsat: Vec<&Vec<Lit>> = self.groups[g].iter().filter(|clause| falsified(&value, clause)).collect();
+            let mut vars: Vec<usize> = unsat.iter().flat_map(|clause| clause.iter().map(|lit| lit.var().idx())).collect();
+            vars.sort_unstable();
+            vars.dedup();
+
+            for v in vars {
+                value[v] = !value[v];
+                if unsat.iter().all(|clause| !falsified(&value, clause)) {
+                    let mut reached = None;
+                    let mut single = true;
+                    for &index in self.occurs[v].iter() {
+                        let h = index.0;
+                        if (h == HARD || active[h]) && falsified(&value, self.clause(index)) {
+                            single = h != HARD && h != g && reached.is_none_or(|r| r == h);
+                            reached = Some(h);
+                            if !single {
+                                break;
+                            }
+                        }
+                    }
+
+                    if let (true, Some(h)) = (single, reached) {
+                        if !necessary[h] {
+                            necessary[h] = true;
+                            stack.push((h, value.clone()));
+                        }
+                    }
+                }
+                value[v] = !value[v];
+            }
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use std::{mem, thread};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use crate::sat::{SolveRes, Solver, Stats};
+use crate::sat::formula::{Idx, Lit, Var};
+use crate::sat::minisat::{self, CCMinMode, ClauseExchange, PhaseSaving, RestartPolicy};
+use crate::sat::minisat::budget::Budget;
+
+
+#[derive(Clone, Copy, Debug)]
+pub struct PortfolioSettings {
+    pub threads: usize,
+    pub simp: bool, // Run 'SimpSolver' workers ('CoreSolver' workers use 'base.core').
+    pub base: minisat::SimpSettings, // Settings of the first worker, the others are diversified from them.
+    pub share_max_len: usize, // Share learnt clauses of at most this length...
+    pub share_max_lbd: u32,   // ...and at most this LBD.
+}
+
+impl Default for PortfolioSettings {
+    fn default() -> Self {
+        PortfolioSettings {
+            threads: 4,
+            simp: true,
+            base: Default::default(),
+            share_max_len: 30,
+            share_max_lbd: 2,
+        }
+    }
+}
+
+
+// Interval of checking the user's budget for an interrupt while workers are running.
+const INTERRUPT_POLL: Duration = Duration::from_millis(10);
+
+
+// Runs differently configured solvers on the same formula in parallel threads. The first worker
+// to finish decides the result and interrupts the others. Workers share good learnt clauses. Every
+// call of 'solve_limited' starts the workers from scratch.
+pub struct Portfolio {
+    settings: PortfolioSettings,
+    vars: Vec<(Option<bool>, bool)>,
+    clauses: Vec<Vec<Lit>>,
+    stats: Stats,
+}
+
+impl Solver for Portfolio {
+    fn n_vars(&self) -> usize {
+        self.vars.len()
+    }
+
+    fn n_clauses(&self) -> usize {
+        self.clauses.len()
+    }
+
+    fn new_var(&mut self, upol: Option<bool>, dvar: bool) -> Var {
+        let v = Var::unidx(self.vars.len());
+        self.vars.push((upol, dvar));
+        v
+    }
+
+    fn add_clause(&mut self, clause: &[Lit]) -> bool {
+        self.clauses.push(clause.to_vec());
+        !clause.is_empty()
+    }
+
+    // Every worker preprocesses the formula itself.
+    fn preprocess(&mut self, _: &Budget) -> bool {
+        !self.clauses.iter().any(|c| c.is_empty())
+    }
+
+    fn solve_limited(mut self, budget: &Budget, assumptions: &[Lit]) -> SolveRes<Self> {
+        let threads = self.settings.threads.max(1);
+        let budgets: Vec<Budget> = (0..threads)
+            .map(|_| budget.share(threads as u64, self.stats.conflicts, self.stats.propagations))
+            .collect();
+        let stop = || budgets.iter().for_each(Budget::interrupt);
+        let inboxes = Arc::new((0..threads).map(|_| Mutex::new(Vec::new())).collect::<Vec<_>>());
+        let (tx, rx) = mpsc::channel();
+
+        let (result, stats) = thread::scope(|scope| {
+            for (id, budget) in budgets.iter().enumerate() {
+                let exchange = Exchange {
+                    id,
+                    n_vars: self.vars.len(),
+                    max_len: self.settings.share_max_len,
+                    max_lbd: self.settings.share_max_lbd,
+                    inboxes: inboxes.clone(),
+                    outbox: Vec::new(),
+                };
+
+                let tx = tx.clone();
+                let this = &self;
+                scope.spawn(move || {
+                    let _ = tx.send(this.worker(id, exchange, budget, assumptions));
+                });
+            }
+            drop(tx);
+
+            let mut result = None;
+            let mut stats = Stats::default();
+            loop {
+                match rx.recv_timeout(INTERRUPT_POLL) {
+                    Ok(res) => {
+                        add_stats(&mut stats, worker_stats(&res));
+                        let decided = match result {
+                            Some(ref r) => is_final(r),
+                            None => false,
+                        };
+
+                        if is_final(&res) && !decided {
+                            stop();
+                            result = Some(res);
+                        } else if result.is_none() {
+                            result = Some(res);
+                        }
+                    }
+
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        if budget.interrupted_now() {
+                            stop();
+                        }
+                    }
+
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+
+            (result, stats)
+        });
+
+        add_stats(&mut self.stats, &stats);
+        match result {
+            Some(SolveRes::UnSAT(_)) => SolveRes::UnSAT(stats),
+            Some(SolveRes::SAT(model, _)) => SolveRes::SAT(model, stats),
+            Some(SolveRes::Interrupted(c, _)) => SolveRes::Interrupted(c, self),
+            None => SolveRes::Interrupted(0.0, self),
+        }
+    }
+
+    fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        add_stats(&mut stats, &self.stats);
+        stats
+    }
+}
+
+impl Portfolio {
+    pub fn new(settings: PortfolioSettings) -> Self {
+        Portfolio {
+            settings,
+            vars: Vec::new(),
+            clauses: Vec::new(),
+            stats: Stats::default(),
+        }
+    }
+
+    // Solves the formula with the settings of the worker 'id'. Interrupted workers return their stats
+    // in place of the solver.
+    fn worker(&self, id: usize, exchange: Exchange, budget: &Budget, assumptions: &[Lit]) -> SolveRes<Stats> {
+        let settings = diversify(&self.settings.base, id);
+        if self.settings.simp {
+            let mut solver = minisat::SimpSolver::new(settings);
+            solver.set_exchange(Box::new(exchange));
+            self.run(solver, budget, assumptions)
+        } else {
+            let mut solver = minisat::CoreSolver::new(settings.core);
+            solver.set_exchange(Box::new(exchange));
+            self.run(solver, budget, assumptions)
+        }
+    }
+
+    fn run<S: Solver>(&self, mut solver: S, budget: &Budget, assumptions: &[Lit]) -> SolveRes<Stats> {
+        for &(upol, dvar) in self.vars.iter() {
+            solver.new_var(upol, dvar);
+        }
+
+        for clause in self.clauses.iter() {
+            if !solver.add_clause(clause) {
+                return SolveRes::UnSAT(solver.stats());
+            }
+        }
+
+        if !solver.preprocess(budget) {
+            return SolveRes::UnSAT(solver.stats());
+        }
+
+        match solver.solve_limited(budget, assumptions) {
+            SolveRes::UnSAT(stats) => SolveRes::UnSAT(stats),
+            SolveRes::SAT(model, stats) => SolveRes::SAT(model, stats),
+            SolveRes::Interrupted(c, solver) => SolveRes::Interrupted(c, solver.stats()),
+        }
+    }
+}
+
+
+fn is_final(res: &SolveRes<Stats>) -> bool {
+    !matches!(res, SolveRes::Interrupted(_, _))
+}
+
+fn worker_stats(res: &SolveRes<Stats>) -> &Stats {
+    match res {
+        SolveRes::UnSAT(stats) | SolveRes::SAT(_, stats) | SolveRes::Interrupted(_, stats) => stats,
+    }
+}
+
+
+// Settings of the worker 'id': the first one runs the base settings, the others vary the random
+// seed, restart policy, phase saving and conflict clause minimization.
+fn diversify(base: &minisat::SimpSettings, id: usize) -> minisat::SimpSettings {
+    let mut s = *base;
+    if id == 0 {
+        return s;
+    }
+
+    s.core.heur.random_seed += (id * 1000003) as f64;
+    s.core.heur.rnd_init_act = true;
+
+    match id % 4 {
+        1 => {
+            s.core.search.restart.policy = RestartPolicy::Glucose;
+        }
+        2 => {
+            s.core.search.restart.policy = RestartPolicy::Ema;
+            s.core.search.mode.switching = true;
+        }
+        3 => {
+            s.core.search.restart.policy = RestartPolicy::Static;
+            s.core.search.restart.luby_restart = false;
+        }
+        _ => {
+            s.core.search.restart.policy = RestartPolicy::Static;
+            s.core.search.rephase.enabled = true;
+        }
+    }
+
+    if id % 3 == 2 {
+        s.core.heur.phase_saving = PhaseSaving::Limited;
+    }
+
+    if id % 5 == 4 {
+        s.core.ccmin_mode = CCMinMode::Basic;
+    }
+
+    s
+}
+
+
+// Learnt clauses are collected locally and passed to the inboxes of the other workers in batches,
+// when the worker imports clauses from its own inbox (on restarts).
+struct Exchange {
+    id: usize,
+    n_vars: usize, // Variables added by the workers themselves (e.g. by BVA) are not shared.
+    max_len: usize,
+    max_lbd: u32,
+    inboxes: Arc<Vec<Mutex<Vec<Vec<Lit>>>>>,
+    outbox: Vec<Vec<Lit>>,
+}
+
+impl ClauseExchange for Exchange {
+    fn export(&mut self, clause: &[Lit], lbd: u32) {
+        if clause.len() <= self.max_len
+            && lbd <= self.max_lbd
+            && clause.iter().all(|lit| lit.var().idx() < self.n_vars)
+        {
+            self.outbox.push(clause.to_vec());
+        }
+    }
+
+    fn import(&mut self) -> Vec<Vec<Lit>> {
+        if !self.outbox.is_empty() {
+            for (id, inbox) in self.inboxes.iter().enumerate() {
+                if id != self.id {
+                    inbox.lock().unwrap().extend(self.outbox.iter().cloned());
+                }
+            }
+            self.outbox.clear();
+        }
+
+        mem::take(&mut *self.inboxes[self.id].lock().unwrap())
+    }
+}
+
+
+fn add_stats(total: &mut Stats, stats: &Stats) {
+    total.solves += stats.solves;
+    total.restarts += stats.restarts;
+    total.decisions += stats.decisions;
+    total.rnd_decisions += stats.rnd_decisions;
+    total.conflicts += stats.conflicts;
+    total.propagations += stats.propagations;
+    total.tot_literals += stats.tot_literals;
+    total.del_literals += stats.del_literals;
+    total.learnts += stats.learnts;
+    total.learnt_lbds += stats.learnt_lbds;
+    total.conflict_trails += stats.conflict_trails;
+    total.reductions += stats.reductions;
+    total.garbage_collections += stats.garbage_collections;
+    total.eliminated_vars += stats.eliminated_vars;
+    total.asymm_lits += stats.asymm_lits;
+    total.equiv_vars += stats.equiv_vars;
+    total.blocked_clauses += stats.blocked_clauses;
+    total.added_vars += stats.added_vars;
+    total.vivified_clauses += stats.vivified_clauses;
+    total.vivified_literals += stats.vivified_literals;
+    total.gates += stats.gates;
+    total.chrono_backtracks += stats.chrono_backtracks;
+    total.blocked_restarts += stats.blocked_restarts;
+    total.mode_switches += stats.mode_switches;
+    total.rephases += stats.rephases;
+    total.walk_flips += stats.walk_flips;
+}
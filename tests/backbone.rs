@@ -0,0 +1,61 @@
+use minisat_rust::sat::{backbone, dimacs, minisat, IncrementalRes, IncrementalSolver};
+use minisat_rust::sat::minisat::budget::Budget;
+use minisat_rust::sat::formula::{Lit, Var};
+
+pub mod common;
+use common::*;
+
+
+#[test]
+fn backbone() {
+    let mut found = 0;
+    for seed in 0..100 {
+        let cnf = random_cnf(seed);
+        let mut reference = minisat::CoreSolver::new(Default::default());
+        let backward_subst = dimacs::parse(cnf.as_bytes(), &mut reference, false).expect("IO Error");
+        let vars: Vec<Var> = backward_subst.iter().map(|(v, _)| v).collect();
+
+        // Literals whose negation is unsatisfiable, tested one by one:
+        let budget = Budget::new();
+        let expected =
+            match reference.solve_incremental(&budget, &[]) {
+                IncrementalRes::Sat(model) => {
+                    let mut lits: Vec<Lit> = model.into_iter().filter(|lit| vars.contains(&lit.var())).collect();
+                    lits.retain(|&lit| matches!(reference.solve_incremental(&budget, &[!lit]), IncrementalRes::UnSat(_)));
+                    lits.sort_by_key(|lit| lit.var());
+                    found += lits.len();
+                    Some(lits)
+                }
+                _ => None,
+            };
+
+        let mut core = minisat::CoreSolver::new(Default::default());
+        dimacs::parse(cnf.as_bytes(), &mut core, false).expect("IO Error");
+        // The variables are frozen by the backbone computation:
+        let mut simp = minisat::SimpSolver::new(Default::default());
+        dimacs::parse(cnf.as_bytes(), &mut simp, false).expect("IO Error");
+
+        for res in [backbone::backbone(&mut core, &budget, &vars), backbone::backbone(&mut simp, &budget, &vars)].iter() {
+            match (res, &expected) {
+                (backbone::BackboneRes::Backbone(lits), Some(expected)) => assert_eq!(lits, expected, "seed {}", seed),
+                (backbone::BackboneRes::UnSat, None) => {}
+                _ => panic!("Different outcomes for seed {}", seed),
+            }
+        }
+    }
+    assert!(found > 0, "No backbone literals");
+}
+
+#[test]
+fn backbone_eliminable() {
+    // Every variable is eliminated unless frozen:
+    let cnf = "p cnf 3 4\n1 2 0\n1 -2 0\n-3 1 0\n3 -1 0\n";
+    let mut simp = minisat::SimpSolver::new(Default::default());
+    let backward_subst = dimacs::parse(cnf.as_bytes(), &mut simp, false).expect("IO Error");
+    let vars: Vec<Var> = backward_subst.iter().map(|(v, _)| v).collect();
+
+    match backbone::backbone(&mut simp, &Budget::new(), &vars) {
+        backbone::BackboneRes::Backbone(lits) => assert_eq!(lits, vec![vars[0].pos_lit(), vars[2].pos_lit()]),
+        _ => panic!("Unexpected result"),
+    }
+}
@@ -0,0 +1,108 @@
+use minisat_rust::sat::{dimacs, minisat, SolveRes, Solver};
+use minisat_rust::sat::minisat::Checkpoint;
+use minisat_rust::sat::minisat::budget::Budget;
+
+pub mod common;
+use common::*;
+
+
+// Solves with a roundtrip through a checkpoint into a new solver every 'every' conflicts.
+fn solve_resumed<S: minisat::Checkpoint, F: Fn() -> S>(new: F, cnf: &str, every: u64) -> bool {
+    let mut solver = new();
+    let backward_subst = dimacs::parse(cnf.as_bytes(), &mut solver, false).expect("IO Error");
+
+    let mut budget = Budget::new();
+    budget.off();
+    if !solver.preprocess(&budget) {
+        return false;
+    }
+
+    loop {
+        budget.set_conflict_limit(solver.stats().conflicts + every);
+        match solver.solve_limited(&budget, &[]) {
+            SolveRes::SAT(model, _) => {
+                assert!(dimacs::validate_model(cnf.as_bytes(), &backward_subst, &model).expect("IO Error"), "Invalid model");
+                return true;
+            }
+
+            SolveRes::UnSAT(_) => return false,
+
+            SolveRes::Interrupted(_, s) => {
+                let mut w = minisat::CheckpointWriter::new(Vec::new()).expect("IO Error");
+                s.checkpoint(&mut w).expect("IO Error");
+                let bytes = w.finish().expect("IO Error");
+
+                solver = new();
+                let mut r = minisat::CheckpointReader::new(&bytes[..]).expect("IO Error");
+                solver.resume(&mut r).expect("IO Error");
+                assert_eq!(solver.n_vars(), s.n_vars());
+                assert_eq!(solver.stats().conflicts, s.stats().conflicts);
+            }
+        }
+    }
+}
+
+#[test]
+fn checkpoints() {
+    let core = || minisat::CoreSolver::new(Default::default());
+    let simp = || minisat::SimpSolver::new(Default::default());
+    assert!(!solve_resumed(core, &pigeonhole_cnf(7, 6), 100));
+    assert!(!solve_resumed(simp, &pigeonhole_cnf(7, 6), 100));
+
+    for seed in 0..50 {
+        let cnf = random_cnf(seed);
+        let expected = solve(minisat::CoreSolver::new(Default::default()), &cnf);
+        assert_eq!(solve_resumed(core, &cnf, 1), expected, "seed {}", seed);
+        assert_eq!(solve_resumed(simp, &cnf, 1), expected, "seed {}", seed);
+    }
+
+    // A checkpoint can't be taken before the preprocessing:
+    let mut w = minisat::CheckpointWriter::new(Vec::new()).expect("IO Error");
+    assert!(simp().checkpoint(&mut w).is_err());
+    assert!(minisat::CheckpointReader::new(&b"MINISAT\0\x01\0\0\0\0\0\0\0"[..]).is_err());
+}
+
+// Resuming restores every part of the state, so that checkpointing the resumed solver gives the same
+// bytes. The schedule continues across the chunks: the modes switch although no chunk is as long as
+// the first focused phase.
+#[test]
+fn checkpoint_schedule() {
+    let mut settings = minisat::CoreSettings::default();
+    settings.heur.stable_branching = minisat::Branching::Vmtf;
+    settings.search.restart.policy = minisat::RestartPolicy::Ema;
+    settings.search.mode.switching = true;
+    settings.search.mode.init_conflicts = 50;
+    settings.search.rephase.enabled = true;
+    settings.search.rephase.interval = 20;
+
+    let mut solver = minisat::CoreSolver::new(settings);
+    dimacs::parse(pigeonhole_cnf(7, 6).as_bytes(), &mut solver, false).expect("IO Error");
+    let mut budget = Budget::new();
+    budget.off();
+    assert!(solver.preprocess(&budget));
+
+    loop {
+        budget.set_conflict_limit(solver.stats().conflicts + 20);
+        match solver.solve_limited(&budget, &[]) {
+            SolveRes::UnSAT(stats) => {
+                assert!(stats.mode_switches > 0, "No mode switches");
+                assert!(stats.rephases > 0, "No rephasing");
+                break;
+            }
+
+            SolveRes::SAT(_, _) => panic!("Unexpected result"),
+
+            SolveRes::Interrupted(_, s) => {
+                let mut w = minisat::CheckpointWriter::new(Vec::new()).expect("IO Error");
+                s.checkpoint(&mut w).expect("IO Error");
+                let bytes = w.finish().expect("IO Error");
+
+                solver = minisat::CoreSolver::new(settings);
+                solver.resume(&mut minisat::CheckpointReader::new(&bytes[..]).expect("IO Error")).expect("IO Error");
+                let mut w = minisat::CheckpointWriter::new(Vec::new()).expect("IO Error");
+                solver.checkpoint(&mut w).expect("IO Error");
+                assert!(w.finish().expect("IO Error") == bytes, "Different checkpoint after {} conflicts", s.stats().conflicts);
+            }
+        }
+    }
+}
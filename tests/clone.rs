@@ -0,0 +1,59 @@
+use minisat_rust::sat::{dimacs, minisat, IncrementalRes, IncrementalSolver, SolveRes, Solver};
+use minisat_rust::sat::minisat::budget::Budget;
+use minisat_rust::sat::formula::{Idx, Var};
+
+pub mod common;
+use common::*;
+
+
+#[test]
+fn clone_solvers() {
+    // A clone interrupted in the middle of the search goes on exactly like the original:
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse(pigeonhole_cnf(8, 7).as_bytes(), &mut solver, false).expect("IO Error");
+    let mut budget = Budget::new();
+    budget.set_conflict_limit(2000);
+    let solver =
+        match solver.solve_limited(&budget, &[]) {
+            SolveRes::Interrupted(_, s) => s,
+            _ => panic!("Unexpected result"),
+        };
+
+    budget.off();
+    let interrupted = solver.stats().conflicts;
+    let fork = solver.clone();
+    let conflicts = [solver.clone(), fork].iter_mut().map(|s| {
+        assert!(matches!(s.solve_incremental(&budget, &[]), IncrementalRes::UnSat(_)));
+        s.stats().conflicts
+    }).collect::<Vec<_>>();
+    assert_eq!(conflicts[0], conflicts[1]);
+    assert!(conflicts[0] > interrupted);
+    assert_eq!(solver.stats().conflicts, interrupted);
+
+    // Forks after the preprocessing solve under different assumptions:
+    for seed in 0..50 {
+        let cnf = random_cnf(seed);
+        let expected = solve(minisat::CoreSolver::new(Default::default()), &cnf);
+
+        let mut solver = minisat::SimpSolver::new(Default::default());
+        let backward_subst = dimacs::parse(cnf.as_bytes(), &mut solver, false).expect("IO Error");
+        let x = Var::unidx(0).pos_lit();
+        solver.set_frozen(x.var(), true);
+        assert!(solver.preprocess(&budget) || !expected);
+
+        let mut sat = false;
+        for &assumption in [x, !x].iter() {
+            let mut fork = solver.clone();
+            match fork.solve_incremental(&budget, &[assumption]) {
+                IncrementalRes::Sat(model) => {
+                    assert!(model.contains(&assumption));
+                    assert!(dimacs::validate_model(cnf.as_bytes(), &backward_subst, &model).expect("IO Error"), "Invalid model");
+                    sat = true;
+                }
+                IncrementalRes::UnSat(_) => {}
+                IncrementalRes::Interrupted(_) => panic!("Unexpected result"),
+            }
+        }
+        assert_eq!(sat, expected, "seed {}", seed);
+    }
+}
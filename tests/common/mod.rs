@@ -0,0 +1,231 @@
+use minisat_rust::sat::{dimacs, minisat, SolveRes, Solver, Stats};
+use minisat_rust::sat::minisat::budget::Budget;
+use std::sync::{Arc, Mutex};
+
+
+// Simple deterministic generator, so every failure is reproducible by its seed.
+pub struct Lcg(pub u64);
+
+impl Lcg {
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % n
+    }
+
+    pub fn lit(&mut self, vars: usize) -> i32 {
+        let v = (self.below(vars) + 1) as i32;
+        if self.below(2) == 0 { v } else { -v }
+    }
+}
+
+
+// Random 3-SAT near the threshold mixed with binary clauses and equivalence chains, so that every
+// simplification technique has something to work on.
+pub fn random_cnf(seed: u64) -> String {
+    let mut rng = Lcg(seed);
+    let vars = 20 + rng.below(20);
+    let mut clauses = Vec::new();
+
+    for _ in 0..rng.below(vars / 2) {
+        let (a, b) = (rng.lit(vars), rng.lit(vars));
+        clauses.push(vec![a, -b]);
+        clauses.push(vec![-a, b]);
+    }
+
+    for _ in 0..rng.below(vars / 2) {
+        clauses.push(vec![rng.lit(vars), rng.lit(vars)]);
+    }
+
+    for _ in 0..(vars * 3 - clauses.len() / 2) {
+        clauses.push(vec![rng.lit(vars), rng.lit(vars), rng.lit(vars)]);
+    }
+
+    let mut cnf = format!("p cnf {} {}\n", vars, clauses.len());
+    for clause in clauses.iter() {
+        for lit in clause.iter() {
+            cnf.push_str(&format!("{} ", lit));
+        }
+        cnf.push_str("0\n");
+    }
+    cnf
+}
+
+
+// Random circuit of AND, XOR and ITE gates (Tseitin encoded) with random constraints on the
+// signals, so that variable elimination finds gate definitions.
+pub fn circuit_cnf(seed: u64) -> String {
+    let mut rng = Lcg(seed);
+    let inputs = 6 + rng.below(6);
+    let vars = inputs + 10 + rng.below(20);
+    let mut clauses = Vec::new();
+
+    for x in (inputs + 1)..(vars + 1) {
+        let x = x as i32;
+        let a = rng.lit(x as usize - 1);
+        let b = rng.lit(x as usize - 1);
+        match rng.below(3) {
+            0 => {
+                clauses.push(vec![-x, a]);
+                clauses.push(vec![-x, b]);
+                clauses.push(vec![x, -a, -b]);
+            }
+            1 => {
+                clauses.push(vec![-x, a, b]);
+                clauses.push(vec![-x, -a, -b]);
+                clauses.push(vec![x, -a, b]);
+                clauses.push(vec![x, a, -b]);
+            }
+            _ => {
+                let c = rng.lit(x as usize - 1);
+                clauses.push(vec![-x, -c, a]);
+                clauses.push(vec![-x, c, b]);
+                clauses.push(vec![x, -c, -a]);
+                clauses.push(vec![x, c, -b]);
+            }
+        }
+    }
+
+    for _ in 0..(vars * 2) {
+        clauses.push(vec![rng.lit(vars), rng.lit(vars), rng.lit(vars)]);
+    }
+
+    let mut cnf = format!("p cnf {} {}\n", vars, clauses.len());
+    for clause in clauses.iter() {
+        for lit in clause.iter() {
+            cnf.push_str(&format!("{} ", lit));
+        }
+        cnf.push_str("0\n");
+    }
+    cnf
+}
+
+
+// Pigeonhole principle with the pairwise at-most-one encoding for every hole.
+pub fn pigeonhole_cnf(pigeons: usize, holes: usize) -> String {
+    let var = |p: usize, h: usize| (p * holes + h + 1) as i32;
+    let mut clauses = Vec::new();
+    for p in 0..pigeons {
+        clauses.push((0..holes).map(|h| var(p, h)).collect::<Vec<_>>());
+    }
+    for h in 0..holes {
+        for p in 0..pigeons {
+            for q in (p + 1)..pigeons {
+                clauses.push(vec![-var(p, h), -var(q, h)]);
+            }
+        }
+    }
+
+    let mut cnf = format!("p cnf {} {}\n", pigeons * holes, clauses.len());
+    for clause in clauses.iter() {
+        for lit in clause.iter() {
+            cnf.push_str(&format!("{} ", lit));
+        }
+        cnf.push_str("0\n");
+    }
+    cnf
+}
+
+
+pub fn solve<S: Solver>(solver: S, cnf: &str) -> bool {
+    solve_stats(solver, cnf).0
+}
+
+// Solves like 'solve', also returns the statistics of the solver.
+pub fn solve_stats<S: Solver>(mut solver: S, cnf: &str) -> (bool, Stats) {
+    let backward_subst = dimacs::parse(cnf.as_bytes(), &mut solver, false).expect("IO Error");
+
+    let mut budget = Budget::new();
+    budget.off();
+    if !solver.preprocess(&budget) {
+        return (false, solver.stats());
+    }
+
+    match solver.solve_limited(&budget, &[]) {
+        SolveRes::SAT(model, stats) => {
+            assert!(dimacs::validate_model(cnf.as_bytes(), &backward_subst, &model).expect("IO Error"), "Invalid model");
+            (true, stats)
+        }
+        SolveRes::UnSAT(stats) => (false, stats),
+        SolveRes::Interrupted(_, _) => panic!("Unexpected result"),
+    }
+}
+
+
+// Compares the results of the configured simplification with those of the plain solver. Returns the
+// statistics of the simplifying solver for every formula.
+pub fn check_simp<F: Fn(&mut minisat::SimpSettings)>(configure: F) -> Vec<Stats> {
+    check_simp_on(random_cnf, configure)
+}
+
+// Like 'check_simp', on the formulas generated by 'cnf' from the seeds.
+pub fn check_simp_on<G: Fn(u64) -> String, F: Fn(&mut minisat::SimpSettings)>(cnf: G, configure: F) -> Vec<Stats> {
+    (0..300).map(|seed| {
+        let cnf = cnf(seed);
+        let expected = solve(minisat::CoreSolver::new(Default::default()), &cnf);
+
+        let mut settings = minisat::SimpSettings::default();
+        configure(&mut settings);
+        let (res, stats) = solve_stats(minisat::SimpSolver::new(settings), &cnf);
+        assert_eq!(res, expected, "Different outcomes for seed {}", seed);
+        stats
+    }).collect()
+}
+
+// Solves the pigeonhole formulas of 'sizes' with the configured simplifying solver and checks that
+// only those with enough holes are satisfiable. Returns the statistics for every formula.
+pub fn check_pigeonholes<F: Fn(&mut minisat::SimpSettings)>(configure: F, sizes: &[(usize, usize)]) -> Vec<Stats> {
+    sizes.iter().map(|&(pigeons, holes)| {
+        let mut settings = minisat::SimpSettings::default();
+        configure(&mut settings);
+        let (res, stats) = solve_stats(minisat::SimpSolver::new(settings), &pigeonhole_cnf(pigeons, holes));
+        assert_eq!(res, pigeons <= holes, "Pigeonhole {} {}", pigeons, holes);
+        stats
+    }).collect()
+}
+
+
+#[derive(Default)]
+pub struct Events {
+    pub restarts: u64,
+    pub conflicts: u64,
+    pub reductions: u64,
+    pub collections: u64,
+    pub simplifications: u64,
+    pub removed: usize,
+    pub snapshots: Vec<minisat::Progress>,
+}
+
+pub struct Recorder(pub Arc<Mutex<Events>>);
+
+impl minisat::Observer for Recorder {
+    fn restarted(&mut self, progress: &minisat::Progress) {
+        let mut events = self.0.lock().unwrap();
+        events.restarts += 1;
+        events.snapshots.push(*progress);
+    }
+
+    fn conflict(&mut self, len: usize, lbd: u32) {
+        assert!(len > 0 && lbd as usize <= len);
+        self.0.lock().unwrap().conflicts += 1;
+    }
+
+    fn reduced(&mut self, before: usize, after: usize) {
+        assert!(after <= before);
+        self.0.lock().unwrap().reductions += 1;
+    }
+
+    fn garbage_collected(&mut self, before: usize, after: usize) {
+        assert!(after <= before);
+        self.0.lock().unwrap().collections += 1;
+    }
+
+    fn simplified(&mut self, removed: usize) {
+        let mut events = self.0.lock().unwrap();
+        events.simplifications += 1;
+        events.removed += removed;
+    }
+
+    fn progress(&mut self, progress: &minisat::Progress) {
+        self.0.lock().unwrap().snapshots.push(*progress);
+    }
+}
@@ -0,0 +1,36 @@
+use minisat_rust::sat::minisat;
+use minisat_rust::sat::formula::Lit;
+use std::sync::{mpsc, Arc};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub mod common;
+use common::*;
+
+
+#[test]
+fn clause_exchange_callbacks() {
+    // Clauses learnt by one solver are imported by another one solving the same formula:
+    let check = |cnf: &str| {
+        let (tx, rx) = mpsc::channel();
+        let exported = Arc::new(AtomicUsize::new(0));
+        let count = exported.clone();
+
+        let mut first = minisat::CoreSolver::new(Default::default());
+        first.set_exchange(Box::new(minisat::CallbackExchange::new(8, 4, Some(Box::new(move |clause: &[Lit], lbd: u32| {
+            assert!(clause.len() <= 8 && lbd <= 4, "Clause not filtered");
+            count.fetch_add(1, Ordering::Relaxed);
+            tx.send(clause.to_vec()).unwrap();
+        })), None)));
+        let expected = solve(first, cnf);
+
+        let mut second = minisat::CoreSolver::new(Default::default());
+        second.set_exchange(Box::new(minisat::CallbackExchange::new(0, 0, None, Some(rx))));
+        assert_eq!(solve(second, cnf), expected);
+        exported.load(Ordering::Relaxed)
+    };
+
+    for seed in 0..100 {
+        check(&random_cnf(seed));
+    }
+    assert!(check(&pigeonhole_cnf(8, 7)) > 0, "No clauses exported");
+}
@@ -0,0 +1,47 @@
+use minisat_rust::sat::{dimacs, minisat, IncrementalRes, Solver};
+use minisat_rust::sat::minisat::budget::Budget;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub mod common;
+use common::*;
+
+
+#[test]
+fn background_solve() {
+    let events = Arc::new(Mutex::new(Events::default()));
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    solver.set_observer(Box::new(Recorder(events.clone())));
+    dimacs::parse(pigeonhole_cnf(11, 10).as_bytes(), &mut solver, false).expect("IO Error");
+
+    let handle = minisat::SolveHandle::spawn(solver, Budget::new(), Vec::new());
+    while handle.progress().conflicts < 1000 && !handle.is_finished() {
+        thread::sleep(Duration::from_millis(1));
+    }
+    handle.interrupt();
+    let (mut solver, res) = handle.join();
+    assert!(matches!(res, IncrementalRes::Interrupted(_)));
+
+    // The observer of the user got every event and is back:
+    assert!(events.lock().unwrap().conflicts >= 1000);
+    assert_eq!(events.lock().unwrap().conflicts, solver.stats().learnts);
+    assert!(solver.take_observer().is_some());
+
+    // Results of the worker are those of a solve in place:
+    for seed in 0..20 {
+        let cnf = random_cnf(seed);
+        let expected = solve(minisat::CoreSolver::new(Default::default()), &cnf);
+
+        let mut solver = minisat::SimpSolver::new(Default::default());
+        let backward_subst = dimacs::parse(cnf.as_bytes(), &mut solver, false).expect("IO Error");
+        match minisat::SolveHandle::spawn(solver, Budget::new(), Vec::new()).join().1 {
+            IncrementalRes::Sat(model) => {
+                assert!(expected, "seed {}", seed);
+                assert!(dimacs::validate_model(cnf.as_bytes(), &backward_subst, &model).expect("IO Error"), "Invalid model");
+            }
+            IncrementalRes::UnSat(_) => assert!(!expected, "seed {}", seed),
+            IncrementalRes::Interrupted(_) => panic!("Unexpected result"),
+        }
+    }
+}
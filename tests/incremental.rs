@@ -0,0 +1,94 @@
+use minisat_rust::sat::{dimacs, minisat, IncrementalRes, IncrementalSolver, Solver};
+use minisat_rust::sat::minisat::budget::Budget;
+
+pub mod common;
+use common::*;
+
+
+#[test]
+fn cube_and_conquer() {
+    for seed in 0..100 {
+        let cnf = random_cnf(seed);
+        let expected = solve(minisat::CoreSolver::new(Default::default()), &cnf);
+
+        let mut cuber = minisat::CoreSolver::new(Default::default());
+        dimacs::parse(cnf.as_bytes(), &mut cuber, false).expect("IO Error");
+        let mut settings = minisat::CubeSettings::default();
+        settings.depth = 4;
+        let cubes = if cuber.preprocess(&Budget::new()) { cuber.cube(&settings) } else { Vec::new() };
+
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        let backward_subst = dimacs::parse(cnf.as_bytes(), &mut solver, false).expect("IO Error");
+        let budget = Budget::new();
+        let mut found = false;
+        for cube in cubes.iter() {
+            match solver.solve_incremental(&budget, cube) {
+                IncrementalRes::Sat(model) => {
+                    assert!(dimacs::validate_model(cnf.as_bytes(), &backward_subst, &model).expect("IO Error"), "Invalid model");
+                    assert!(cube.iter().all(|lit| model.contains(lit)), "Cube violated for seed {}", seed);
+                    found = true;
+                }
+
+                IncrementalRes::UnSat(failed) => {
+                    assert!(failed.iter().all(|lit| cube.contains(lit)), "Not an assumption failed for seed {}", seed);
+                }
+
+                IncrementalRes::Interrupted(_) => panic!("Unexpected result"),
+            }
+        }
+        assert_eq!(found, expected, "Different outcomes for seed {}", seed);
+    }
+}
+
+
+#[test]
+fn icnf_queries() {
+    for seed in 0..100 {
+        let cnf = random_cnf(seed);
+        let clauses: Vec<&str> = cnf.lines().skip(1).collect();
+        let queries = [(clauses.len() / 2, "1 -2 3 "), (clauses.len(), "-4 5 "), (clauses.len(), "")];
+
+        // Every query is answered for the clauses given before it:
+        let mut icnf = String::from("p inccnf\n");
+        let mut prefixes = Vec::new();
+        let mut given = 0;
+        for &(n, assumptions) in queries.iter() {
+            for clause in clauses[given..n].iter() {
+                icnf.push_str(clause);
+                icnf.push('\n');
+            }
+            given = n;
+            icnf.push_str(&format!("a {}0\n", assumptions));
+            prefixes.push(format!("p cnf 0 0\n{}\n", clauses[..n].join("\n")));
+        }
+
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        let mut answered = 0;
+        dimacs::parse_icnf(icnf.as_bytes(), &mut solver, |solver, assumptions, backward_subst| {
+            let (_, raw) = queries[answered];
+            let mut units = prefixes[answered].clone();
+            for lit in raw.split_whitespace() {
+                units.push_str(&format!("{} 0\n", lit));
+            }
+            let expected = solve(minisat::CoreSolver::new(Default::default()), &units);
+
+            match solver.solve_incremental(&Budget::new(), assumptions) {
+                IncrementalRes::Sat(model) => {
+                    assert!(expected, "Different outcomes for seed {}, query {}", seed, answered);
+                    assert!(dimacs::validate_model(units.as_bytes(), backward_subst, &model).expect("IO Error"), "Invalid model");
+                }
+
+                IncrementalRes::UnSat(failed) => {
+                    assert!(!expected, "Different outcomes for seed {}, query {}", seed, answered);
+                    assert!(failed.iter().all(|lit| assumptions.contains(lit)), "Not an assumption failed for seed {}", seed);
+                }
+
+                IncrementalRes::Interrupted(_) => panic!("Unexpected result"),
+            }
+
+            answered += 1;
+            Ok(())
+        }).expect("IO Error");
+        assert_eq!(answered, queries.len());
+    }
+}
@@ -0,0 +1,147 @@
+use minisat_rust::sat::{ipasir, minisat, Solver};
+use std::os::raw::{c_int, c_void};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub mod common;
+use common::*;
+
+
+extern "C" fn count_learnt(data: *mut c_void, clause: *mut i32) {
+    unsafe {
+        assert!(*clause != 0, "Empty learnt clause");
+        *(data as *mut usize) += 1;
+    }
+}
+
+extern "C" fn terminate_now(_: *mut c_void) -> c_int {
+    1
+}
+
+#[test]
+fn ipasir_interface() {
+    for seed in 0..100 {
+        let cnf = random_cnf(seed);
+        let clauses: Vec<Vec<i32>> = cnf.lines().skip(1)
+            .map(|line| line.split_whitespace().map(|x| x.parse().unwrap()).filter(|&x| x != 0).collect())
+            .collect();
+
+        let queries: [&[i32]; 3] = [&[1, -2, 3], &[-4, 5], &[]];
+        let c_solver = ipasir::ipasir_init();
+        let mut simp_solver = ipasir::Ipasir::new(minisat::SimpSolver::new(Default::default()));
+        for clause in clauses.iter() {
+            for &lit in clause.iter() {
+                unsafe { ipasir::ipasir_add(c_solver, lit) };
+                simp_solver.add(lit);
+            }
+            unsafe { ipasir::ipasir_add(c_solver, 0) };
+            simp_solver.add(0);
+        }
+
+        for assumptions in queries.iter() {
+            let mut units = cnf.clone();
+            for lit in assumptions.iter() {
+                units.push_str(&format!("{} 0\n", lit));
+            }
+            let expected = solve(minisat::CoreSolver::new(Default::default()), &units);
+
+            for &lit in assumptions.iter() {
+                unsafe { ipasir::ipasir_assume(c_solver, lit) };
+                simp_solver.assume(lit);
+            }
+            let res = unsafe { ipasir::ipasir_solve(c_solver) };
+            assert_eq!(res, if expected { 10 } else { 20 }, "Different outcomes for seed {}", seed);
+            assert_eq!(simp_solver.solve(), res, "Different outcomes of SimpSolver for seed {}", seed);
+
+            if expected {
+                for clause in clauses.iter() {
+                    assert!(clause.iter().any(|&lit| unsafe { ipasir::ipasir_val(c_solver, lit) } == lit), "Invalid model");
+                    assert!(clause.iter().any(|&lit| simp_solver.val(lit) == lit), "Invalid model of SimpSolver");
+                }
+                assert!(assumptions.iter().all(|&lit| unsafe { ipasir::ipasir_val(c_solver, lit) } == lit), "Assumption violated");
+            } else {
+                for lit in -40..40 {
+                    if unsafe { ipasir::ipasir_failed(c_solver, lit) } != 0 {
+                        assert!(assumptions.contains(&lit), "Not an assumption failed for seed {}", seed);
+                    }
+                }
+            }
+        }
+
+        unsafe { ipasir::ipasir_release(c_solver) };
+    }
+
+    // Literals without a variable:
+    let c_solver = ipasir::ipasir_init();
+    unsafe {
+        for &lit in [1, 0].iter() {
+            ipasir::ipasir_add(c_solver, lit);
+        }
+        ipasir::ipasir_assume(c_solver, 0);
+        ipasir::ipasir_assume(c_solver, i32::MIN);
+        assert_eq!(ipasir::ipasir_solve(c_solver), 10);
+        assert_eq!(ipasir::ipasir_val(c_solver, 1), 1);
+        assert_eq!(ipasir::ipasir_val(c_solver, 0), 0);
+        assert_eq!(ipasir::ipasir_val(c_solver, i32::MIN), 0);
+
+        ipasir::ipasir_assume(c_solver, -1);
+        assert_eq!(ipasir::ipasir_solve(c_solver), 20);
+        assert_eq!(ipasir::ipasir_failed(c_solver, -1), 1);
+        assert_eq!(ipasir::ipasir_failed(c_solver, 0), 0);
+        assert_eq!(ipasir::ipasir_failed(c_solver, i32::MIN), 0);
+        ipasir::ipasir_release(c_solver);
+    }
+
+    // Callbacks:
+    let cnf = pigeonhole_cnf(8, 7);
+    let c_solver = ipasir::ipasir_init();
+    for line in cnf.lines().skip(1) {
+        for lit in line.split_whitespace() {
+            unsafe { ipasir::ipasir_add(c_solver, lit.parse().unwrap()) };
+        }
+    }
+
+    let mut learnt = 0usize;
+    unsafe {
+        ipasir::ipasir_set_learn(c_solver, &mut learnt as *mut usize as *mut c_void, 10, Some(count_learnt));
+        ipasir::ipasir_set_terminate(c_solver, std::ptr::null_mut(), Some(terminate_now));
+        assert_eq!(ipasir::ipasir_solve(c_solver), 0);
+        ipasir::ipasir_set_terminate(c_solver, std::ptr::null_mut(), None);
+        assert_eq!(ipasir::ipasir_solve(c_solver), 20);
+        ipasir::ipasir_release(c_solver);
+    }
+    assert!(learnt > 0, "No learnt clauses passed");
+}
+
+
+#[test]
+#[should_panic(expected = "Invalid literal")]
+fn ipasir_invalid_literal() {
+    let mut solver = ipasir::Ipasir::new(minisat::CoreSolver::new(Default::default()));
+    solver.add(1);
+    solver.add(i32::MIN);
+}
+
+
+#[test]
+fn ipasir_terminate_polls() {
+    let mut solver = ipasir::Ipasir::new(minisat::CoreSolver::new(Default::default()));
+    for line in pigeonhole_cnf(8, 7).lines().skip(1) {
+        for lit in line.split_whitespace() {
+            solver.add(lit.parse().unwrap());
+        }
+    }
+
+    let polls = Arc::new(AtomicU64::new(0));
+    let counter = polls.clone();
+    solver.set_terminate(Some(Box::new(move || {
+        counter.fetch_add(1, Ordering::Relaxed);
+        false
+    })));
+    assert_eq!(solver.solve(), 20);
+
+    let stats = solver.solver().stats();
+    let polls = polls.load(Ordering::Relaxed);
+    assert!(polls > 0, "Terminate not polled");
+    assert!(polls * 100 < stats.decisions + stats.conflicts, "Terminate polled {} times in {} decisions", polls, stats.decisions);
+}
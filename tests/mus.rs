@@ -0,0 +1,82 @@
+use minisat_rust::sat::{dimacs, minisat, mus, IncrementalRes, IncrementalSolver, Solver};
+use minisat_rust::sat::minisat::budget::Budget;
+use minisat_rust::sat::formula::Lit;
+
+pub mod common;
+use common::*;
+
+
+fn satisfiable(n_vars: usize, clauses: Vec<Vec<Lit>>) -> bool {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    for _ in 0..n_vars {
+        solver.new_var(None, true);
+    }
+    for clause in clauses {
+        solver.add_clause(&clause);
+    }
+    matches!(solver.solve_incremental(&Budget::new(), &[]), IncrementalRes::Sat(_))
+}
+
+// Checks the MUS of the groups, or that all of them are satisfiable, with fresh solvers. Returns
+// whether they are unsatisfiable.
+fn check_mus<S: IncrementalSolver>(mut solver: S, gcnf: &str, seed: u64) -> bool {
+    let parsed = dimacs::parse_gcnf(gcnf.as_bytes(), &mut solver, true).expect("IO Error");
+    let n_vars = solver.n_vars();
+    let subset = |groups: &[usize]| -> Vec<Vec<Lit>> {
+        parsed.hard.iter().chain(groups.iter().flat_map(|&g| parsed.groups[g].iter())).cloned().collect()
+    };
+
+    match mus::mus(&mut solver, &Budget::new(), &parsed.hard, &parsed.groups) {
+        mus::MusRes::Mus(groups) => {
+            assert!(!satisfiable(n_vars, subset(&groups)), "seed {}", seed);
+            for i in 0..groups.len() {
+                let mut smaller = groups.clone();
+                smaller.remove(i);
+                assert!(satisfiable(n_vars, subset(&smaller)), "seed {}: group {} is not necessary", seed, groups[i]);
+            }
+            true
+        }
+
+        mus::MusRes::Sat => {
+            let all: Vec<usize> = (0..parsed.groups.len()).collect();
+            assert!(satisfiable(n_vars, subset(&all)), "seed {}", seed);
+            false
+        }
+
+        mus::MusRes::Interrupted => panic!("Interrupted without a budget"),
+    }
+}
+
+#[test]
+fn mus() {
+    let mut found = 0;
+    for seed in 0..100 {
+        // Groups of the clauses of a random CNF, some of them hard:
+        let mut rng = Lcg(seed);
+        let n_groups = 1 + rng.below(30);
+        let cnf = random_cnf(seed);
+        let mut lines = cnf.lines();
+        let header = lines.next().unwrap().replace("p cnf", "p gcnf");
+        let mut gcnf = format!("{} {}\n", header, n_groups);
+        for line in lines {
+            gcnf.push_str(&format!("{{{}}} {}\n", rng.below(n_groups + 1), line));
+        }
+
+        let unsat = check_mus(minisat::CoreSolver::new(Default::default()), &gcnf, seed);
+        assert_eq!(check_mus(minisat::SimpSolver::new(Default::default()), &gcnf, seed), unsat, "seed {}", seed);
+        if unsat {
+            found += 1;
+        }
+    }
+    assert!(found > 0, "No unsatisfiable groups");
+
+    // A plain CNF has a group per clause:
+    let cnf = "p cnf 2 4\n1 2 0\n-1 0\n1 0\n-2 0\n";
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let parsed = dimacs::parse_gcnf(cnf.as_bytes(), &mut solver, true).expect("IO Error");
+    assert!(parsed.hard.is_empty());
+    match mus::mus(&mut solver, &Budget::new(), &parsed.hard, &parsed.groups) {
+        mus::MusRes::Mus(groups) => assert!(groups == vec![0, 1, 3] || groups == vec![1, 2], "{:?}", groups),
+        _ => panic!("Not unsatisfiable"),
+    }
+}
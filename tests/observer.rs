@@ -0,0 +1,55 @@
+use minisat_rust::sat::{dimacs, minisat, SolveRes, Solver};
+use minisat_rust::sat::minisat::budget::Budget;
+use std::sync::{Arc, Mutex};
+
+pub mod common;
+use common::*;
+
+
+#[test]
+fn search_observer() {
+    let events = Arc::new(Mutex::new(Events::default()));
+    let mut solver = minisat::SimpSolver::new(Default::default());
+    solver.set_observer(Box::new(Recorder(events.clone())));
+    dimacs::parse(pigeonhole_cnf(8, 7).as_bytes(), &mut solver, false).expect("IO Error");
+
+    let mut budget = Budget::new();
+    budget.off();
+    let stats =
+        match solver.solve_limited(&budget, &[]) {
+            SolveRes::UnSAT(stats) => stats,
+            _ => panic!("Unexpected result"),
+        };
+
+    let events = events.lock().unwrap();
+    assert!(events.restarts > 0 && events.reductions > 0 && events.collections > 0);
+    assert!(events.conflicts > 0 && events.conflicts <= stats.conflicts);
+    assert_eq!(events.conflicts, stats.learnts);
+    assert_eq!(events.reductions, stats.reductions);
+    assert_eq!(events.collections, stats.garbage_collections);
+    assert!(stats.avg_lbd() >= 1.0 && stats.avg_trail() > 0.0);
+    for pair in events.snapshots.windows(2) {
+        assert!(pair[0].conflicts <= pair[1].conflicts && pair[0].restarts <= pair[1].restarts);
+    }
+    assert!(events.snapshots.iter().all(|p| p.estimate >= 0.0 && p.estimate <= 1.0));
+}
+
+
+#[test]
+fn simplifier_observer() {
+    let events = Arc::new(Mutex::new(Events::default()));
+    let mut solver = minisat::SimpSolver::new(Default::default());
+    solver.set_observer(Box::new(Recorder(events.clone())));
+    let clauses = dimacs::parse(circuit_cnf(0).as_bytes(), &mut solver, false).map(|_| solver.n_clauses()).expect("IO Error");
+
+    let mut budget = Budget::new();
+    budget.off();
+    assert!(solver.preprocess(&budget));
+
+    let events = events.lock().unwrap();
+    let stats = solver.stats();
+    assert!(stats.eliminated_vars > 0);
+    assert_eq!(events.simplifications, 2); // At the ground level, then by the elimination.
+    assert!(events.removed > 0 && events.removed == clauses - solver.n_clauses());
+    assert!(events.collections > 0 && events.collections == stats.garbage_collections);
+}
@@ -0,0 +1,47 @@
+use minisat_rust::sat::{dimacs, minisat, portfolio, SolveRes, Solver};
+use minisat_rust::sat::minisat::budget::Budget;
+
+pub mod common;
+use common::*;
+
+
+#[test]
+fn portfolio() {
+    for seed in 0..100 {
+        let cnf = random_cnf(seed);
+        let expected = solve(minisat::CoreSolver::new(Default::default()), &cnf);
+
+        for &simp in [false, true].iter() {
+            let mut settings = portfolio::PortfolioSettings::default();
+            settings.simp = simp;
+            settings.base.simp.use_bva = true;
+            settings.share_max_len = 100;
+            settings.share_max_lbd = 100;
+            assert_eq!(solve(portfolio::Portfolio::new(settings), &cnf), expected, "Different outcomes for seed {}", seed);
+        }
+    }
+
+    for &(pigeons, holes) in [(6, 6), (7, 6), (8, 7)].iter() {
+        let cnf = pigeonhole_cnf(pigeons, holes);
+        let solver = portfolio::Portfolio::new(Default::default());
+        assert_eq!(solve(solver, &cnf), pigeons <= holes, "Pigeonhole {} {}", pigeons, holes);
+    }
+}
+
+
+#[test]
+fn portfolio_budget() {
+    let cnf = pigeonhole_cnf(11, 10);
+    let mut solver = portfolio::Portfolio::new(Default::default());
+    dimacs::parse(cnf.as_bytes(), &mut solver, false).expect("IO Error");
+
+    let mut budget = Budget::new();
+    budget.set_conflict_limit(1000);
+    match solver.solve_limited(&budget, &[]) {
+        SolveRes::Interrupted(_, solver) => {
+            let conflicts = solver.stats().conflicts;
+            assert!(conflicts > 0 && conflicts <= 1000 + 4, "Conflict limit exceeded: {}", conflicts);
+        }
+        _ => panic!("Conflict limit ignored"),
+    }
+}
@@ -0,0 +1,118 @@
+use minisat_rust::sat::minisat;
+use minisat_rust::sat::formula::{Idx, Lit, Var};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub mod common;
+use common::*;
+
+
+// At most one variable of every group of consecutive variables is true, either propagated or only
+// checked on complete models.
+struct AtMostOne {
+    group: usize,
+    lazy: bool,
+    value: Vec<bool>,
+    trail: Vec<Lit>,
+    lims: Vec<usize>,
+    refined: Arc<AtomicUsize>,
+}
+
+impl AtMostOne {
+    fn true_vars(&self, g: usize) -> Vec<Var> {
+        let end = (g * self.group + self.group).min(self.value.len());
+        (g * self.group..end).filter(|&i| self.value[i]).map(Var::unidx).collect()
+    }
+}
+
+impl minisat::Propagator for AtMostOne {
+    fn assigned(&mut self, lit: Lit) {
+        self.value[lit.var().idx()] = !lit.sign();
+        self.trail.push(lit);
+    }
+
+    fn decided(&mut self) {
+        self.lims.push(self.trail.len());
+    }
+
+    fn backtracked(&mut self, level: usize) {
+        if level < self.lims.len() {
+            for lit in self.trail.drain(self.lims[level]..) {
+                self.value[lit.var().idx()] = false;
+            }
+            self.lims.truncate(level);
+        }
+    }
+
+    fn propagate(&mut self) -> minisat::Propagation {
+        let mut implied = Vec::new();
+        if !self.lazy {
+            for g in 0..(self.value.len() + self.group - 1) / self.group {
+                match self.true_vars(g)[..] {
+                    [] => {}
+                    [x] => {
+                        let end = (g * self.group + self.group).min(self.value.len());
+                        implied.extend((g * self.group..end).map(Var::unidx).filter(|&y| y != x).map(Var::neg_lit));
+                    }
+                    [x, y, ..] => { return minisat::Propagation::Conflict(vec![x.neg_lit(), y.neg_lit()]); }
+                }
+            }
+        }
+        minisat::Propagation::Implied(implied)
+    }
+
+    fn reason(&mut self, lit: Lit) -> Vec<Lit> {
+        let x = lit.var();
+        let y = self.true_vars(x.idx() / self.group).into_iter().find(|&y| y != x).unwrap();
+        vec![lit, y.neg_lit()]
+    }
+
+    fn check_model(&mut self, _: &[Lit]) -> Vec<Vec<Lit>> {
+        let mut clauses = Vec::new();
+        for g in 0..(self.value.len() + self.group - 1) / self.group {
+            if let [x, y, ..] = self.true_vars(g)[..] {
+                clauses.push(vec![x.neg_lit(), y.neg_lit()]);
+            }
+        }
+        assert!(self.lazy || clauses.is_empty(), "Propagation missed a violation");
+        self.refined.fetch_add(clauses.len(), Ordering::Relaxed);
+        clauses
+    }
+}
+
+#[test]
+fn user_propagator() {
+    let refined = Arc::new(AtomicUsize::new(0));
+    for seed in 0..200 {
+        let cnf = random_cnf(seed);
+        let vars: usize = cnf.split_whitespace().nth(2).unwrap().parse().unwrap();
+        let group = 3 + (seed as usize) % 4;
+
+        let mut amo = Vec::new();
+        for x in 1..(vars + 1) {
+            for y in (x + 1)..(vars + 1) {
+                if (x - 1) / group == (y - 1) / group {
+                    amo.push(format!("-{} -{} 0\n", x, y));
+                }
+            }
+        }
+        let clauses: usize = cnf.split_whitespace().nth(3).unwrap().parse().unwrap();
+        let amo_cnf = format!("p cnf {} {}\n{}{}", vars, clauses + amo.len(), cnf.splitn(2, '\n').nth(1).unwrap(), amo.concat());
+        let expected = solve(minisat::CoreSolver::new(Default::default()), &amo_cnf);
+
+        for &lazy in [false, true].iter() {
+            // The DIMACS variables are created in order, variable 'i' has index 'i - 1':
+            let mut solver = minisat::CoreSolver::new(Default::default());
+            solver.set_propagator(Box::new(AtMostOne {
+                group,
+                lazy,
+                value: vec![false; vars],
+                trail: Vec::new(),
+                lims: Vec::new(),
+                refined: refined.clone(),
+            }));
+            assert_eq!(solve(solver, &cnf), expected, "Different outcomes for seed {} (lazy: {})", seed, lazy);
+        }
+    }
+    assert!(refined.load(Ordering::Relaxed) > 0, "No models refined");
+}
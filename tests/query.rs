@@ -0,0 +1,95 @@
+use minisat_rust::sat::{dimacs, minisat, IncrementalRes, IncrementalSolver};
+use minisat_rust::sat::minisat::budget::Budget;
+use minisat_rust::sat::formula::{Idx, Lit, Var};
+use std::collections::HashSet;
+
+pub mod common;
+use common::*;
+
+
+// True literals of the unit propagation fixpoint, none on a conflict.
+fn unit_propagate(clauses: &[Vec<Lit>], assumptions: &[Lit]) -> Option<HashSet<Lit>> {
+    let mut true_lits: HashSet<Lit> = assumptions.iter().cloned().collect();
+    if assumptions.iter().any(|&lit| true_lits.contains(&!lit)) {
+        return None;
+    }
+
+    loop {
+        let mut changed = false;
+        for clause in clauses.iter() {
+            if clause.iter().any(|lit| true_lits.contains(lit)) {
+                continue;
+            }
+
+            let mut open: Vec<Lit> = clause.iter().cloned().filter(|&lit| !true_lits.contains(&!lit)).collect();
+            open.sort();
+            open.dedup();
+            match open.len() {
+                0 => return None,
+                1 => {
+                    true_lits.insert(open[0]);
+                    changed = true;
+                }
+                _ => {}
+            }
+        }
+
+        if !changed {
+            return Some(true_lits);
+        }
+    }
+}
+
+#[test]
+fn propagate_assumptions() {
+    for seed in 0..100 {
+        let cnf = random_cnf(seed);
+        let clauses: Vec<Vec<Lit>> = cnf.lines().skip(1)
+            .map(|line| line.split_whitespace().map(|x| x.parse::<i32>().unwrap()).filter(|&x| x != 0)
+                .map(|x| Var::unidx((x.abs() - 1) as usize).sign_lit(x < 0)).collect())
+            .collect();
+        let expected = solve(minisat::CoreSolver::new(Default::default()), &cnf);
+
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        dimacs::parse(cnf.as_bytes(), &mut solver, false).expect("IO Error");
+
+        let mut rng = Lcg(seed);
+        for _ in 0..20 {
+            let assumptions: Vec<Lit> = (0..(1 + rng.below(4))).map(|_| {
+                let clause = &clauses[rng.below(clauses.len())];
+                let lit = clause[rng.below(clause.len())];
+                if rng.below(2) == 0 { lit } else { !lit }
+            }).collect();
+
+            match (unit_propagate(&clauses, &assumptions), solver.explain_assumptions(&assumptions)) {
+                (Some(fixpoint), Ok(implied)) => {
+                    let mut got: HashSet<Lit> = assumptions.iter().cloned().collect();
+                    for (lit, reason) in implied.iter() {
+                        assert!(!assumptions.contains(lit));
+                        if let Some(ref reason) = *reason {
+                            assert!(reason.iter().all(|l| l == lit || fixpoint.contains(&!*l)) && reason.contains(lit));
+                        }
+                        got.insert(*lit);
+                    }
+                    assert_eq!(got, fixpoint, "seed {}", seed);
+
+                    let lits: Vec<Lit> = implied.iter().map(|&(lit, _)| lit).collect();
+                    assert_eq!(solver.propagate_assumptions(&assumptions), Ok(lits));
+                }
+
+                (None, Err(conflict)) => {
+                    assert!(conflict.assumptions.iter().all(|lit| assumptions.contains(lit)));
+                    assert!(unit_propagate(&clauses, &conflict.assumptions).is_none(), "seed {}", seed);
+                    assert!(!conflict.assumptions.is_empty() || !expected, "seed {}", seed);
+                    // Problem clauses are stored without duplicate literals and literals false at the ground level:
+                    assert!(conflict.clause.is_empty() || clauses.iter().any(|c| conflict.clause.iter().all(|lit| c.contains(lit))));
+                }
+
+                (fixpoint, res) => panic!("seed {}: {:?} vs {:?}", seed, fixpoint.is_some(), res.is_ok()),
+            }
+        }
+
+        // The solver is back at the ground level:
+        assert_eq!(matches!(solver.solve_incremental(&Budget::new(), &[]), IncrementalRes::Sat(_)), expected);
+    }
+}
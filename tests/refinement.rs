@@ -0,0 +1,45 @@
+use minisat_rust::sat::minisat;
+use minisat_rust::sat::formula::{Idx, Lit, Var};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub mod common;
+use common::*;
+
+
+#[test]
+fn model_refinement() {
+    let refinements = Arc::new(AtomicUsize::new(0));
+    for seed in 0..200 {
+        // Random clauses added only when models violate them, over the variables of the formula:
+        let cnf = random_cnf(seed);
+        let vars = cnf.lines().skip(1).flat_map(|line| line.split_whitespace()).map(|lit| lit.parse::<i32>().unwrap().abs()).max().unwrap();
+        let mut rng = Lcg(seed + 1000);
+        let hidden: Vec<Vec<i32>> = (0..vars).map(|_| (0..3).map(|_| rng.lit(vars as usize)).collect()).collect();
+
+        let clauses: usize = cnf.split_whitespace().nth(3).unwrap().parse().unwrap();
+        let mut both = format!("p cnf {} {}\n{}", vars, clauses + hidden.len(), cnf.splitn(2, '\n').nth(1).unwrap());
+        for clause in hidden.iter() {
+            both.push_str(&format!("{} {} {} 0\n", clause[0], clause[1], clause[2]));
+        }
+        let expected = solve(minisat::CoreSolver::new(Default::default()), &both);
+
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        let count = refinements.clone();
+        solver.set_model_refinement(Box::new(move |model: &[Lit]| {
+            let mut value = vec![false; vars as usize];
+            for lit in model.iter() {
+                value[lit.var().idx()] = !lit.sign();
+            }
+
+            let violated: Vec<Vec<Lit>> = hidden.iter()
+                .filter(|clause| clause.iter().all(|&lit| value[(lit.abs() - 1) as usize] != (lit > 0)))
+                .map(|clause| clause.iter().map(|&lit| Var::unidx((lit.abs() - 1) as usize).sign_lit(lit < 0)).collect())
+                .collect();
+            count.fetch_add(violated.len(), Ordering::Relaxed);
+            violated
+        }));
+        assert_eq!(solve(solver, &cnf), expected, "Different outcomes for seed {}", seed);
+    }
+    assert!(refinements.load(Ordering::Relaxed) > 0, "No models refined");
+}
@@ -0,0 +1,145 @@
+use minisat_rust::sat::{dimacs, minisat, SolveRes, Solver};
+use minisat_rust::sat::minisat::budget::Budget;
+
+pub mod common;
+use common::*;
+
+
+#[test]
+fn clause_vivification() {
+    let vivify = |s: &mut minisat::CoreSettings| {
+        s.search.restart.restart_first = 5.0;
+        s.search.vivify.learnts = true;
+        s.search.vivify.clauses = true;
+        s.search.vivify.interval = 0;
+        s.search.vivify.effort = 1.0;
+    };
+
+    let stats = check_simp(|s| vivify(&mut s.core));
+    assert!(stats.iter().any(|s| s.vivified_clauses > 0), "No clauses vivified");
+
+    let stats = check_pigeonholes(|s| vivify(&mut s.core), &[(6, 6), (7, 6), (8, 8), (8, 7)]);
+    assert!(stats.iter().all(|s| s.vivified_literals >= s.vivified_clauses));
+    assert!(stats.iter().any(|s| s.vivified_clauses > 0), "No learnt clauses vivified");
+}
+
+
+#[test]
+fn chronological_backtracking() {
+    for &limit in [0, 1, 5].iter() {
+        check_simp(|s| {
+            s.core.core.chrono = Some(limit);
+        });
+
+        let stats = check_pigeonholes(|s| s.core.core.chrono = Some(limit), &[(6, 6), (7, 6)]);
+        assert!(stats.iter().any(|s| s.chrono_backtracks > 0), "No chronological backtracks with limit {}", limit);
+    }
+}
+
+#[test]
+fn branching_heuristics() {
+    for &branching in [minisat::Branching::Vmtf, minisat::Branching::Lrb, minisat::Branching::Chb].iter() {
+        check_simp(|s| {
+            s.core.heur.branching = branching;
+        });
+        check_pigeonholes(|s| s.core.heur.branching = branching, &[(6, 6), (7, 6)]);
+    }
+}
+
+
+#[test]
+fn dynamic_restarts() {
+    let sizes = [(6, 6), (7, 6)];
+    for &policy in [minisat::RestartPolicy::Glucose, minisat::RestartPolicy::Ema].iter() {
+        for &blocking in [None, Some(1.0)].iter() {
+            let restarts = |policy| {
+                move |s: &mut minisat::CoreSettings| {
+                    s.search.restart.policy = policy;
+                    s.search.restart.lbd_window = 5;
+                    s.search.restart.trail_window = 10;
+                    s.search.restart.blocking = blocking;
+                    s.search.restart.block_min_conflicts = 0;
+                }
+            };
+
+            let mut stats = check_simp(|s| restarts(policy)(&mut s.core));
+
+            let dynamic = check_pigeonholes(|s| restarts(policy)(&mut s.core), &sizes);
+            let luby = check_pigeonholes(|s| restarts(minisat::RestartPolicy::Static)(&mut s.core), &sizes);
+            for ((d, l), (pigeons, holes)) in dynamic.iter().zip(luby.iter()).zip(sizes.iter()) {
+                if pigeons > holes {
+                    assert_ne!((d.restarts, d.conflicts), (l.restarts, l.conflicts), "{:?} restarts like Luby", policy);
+                }
+            }
+            stats.extend(dynamic);
+
+            let blocked: u64 = stats.iter().map(|s| s.blocked_restarts).sum();
+            assert!(stats.iter().any(|s| s.restarts > 1), "No restarts with {:?}", policy);
+            assert_eq!(blocked > 0, blocking.is_some(), "{} restarts blocked with {:?}", blocked, policy);
+        }
+    }
+}
+
+#[test]
+fn stable_mode_and_rephasing() {
+    let modes = |s: &mut minisat::CoreSettings| {
+        s.heur.branching = minisat::Branching::Vmtf;
+        s.search.restart.policy = minisat::RestartPolicy::Ema;
+        s.search.restart.lbd_window = 5;
+        s.search.mode.switching = true;
+        s.search.mode.init_conflicts = 10;
+        s.search.mode.stable_restart.restart_first = 10.0;
+        s.search.rephase.enabled = true;
+        s.search.rephase.interval = 5;
+    };
+
+    check_simp(|s| modes(&mut s.core));
+
+    check_pigeonholes(|s| modes(&mut s.core), &[(6, 6), (8, 8)]);
+    let stats = check_pigeonholes(|s| modes(&mut s.core), &[(7, 6)]);
+    assert!(stats[0].mode_switches > 0, "No mode switches");
+    assert!(stats[0].rephases > 0, "No rephasing");
+}
+
+#[test]
+fn local_search() {
+    let walking = |s: &mut minisat::CoreSettings| {
+        s.search.rephase.enabled = true;
+        s.search.rephase.interval = 5;
+        s.search.local_search.walk = true;
+        s.search.local_search.effort = 1.0;
+    };
+
+    check_simp(|s| walking(&mut s.core));
+
+    check_pigeonholes(|s| walking(&mut s.core), &[(7, 7)]);
+    let stats = check_pigeonholes(|s| walking(&mut s.core), &[(7, 6)]);
+    assert!(stats[0].walk_flips > 0, "No rephasing walks");
+
+    for &algorithm in [minisat::LocalSearchAlgorithm::ProbSat, minisat::LocalSearchAlgorithm::WalkSat].iter() {
+        for seed in 0..100 {
+            let cnf = random_cnf(seed);
+            let expected = solve(minisat::CoreSolver::new(Default::default()), &cnf);
+
+            let mut settings = minisat::CoreSettings::default();
+            settings.search.local_search.standalone = true;
+            settings.search.local_search.algorithm = algorithm;
+            if expected {
+                let (res, stats) = solve_stats(minisat::CoreSolver::new(settings), &cnf);
+                assert!(res, "No model for seed {}", seed);
+                assert_eq!(stats.conflicts, 0, "CDCL search after the local search for seed {}", seed);
+            } else {
+                settings.search.local_search.max_flips = 1000;
+                let mut solver = minisat::CoreSolver::new(settings);
+                dimacs::parse(cnf.as_bytes(), &mut solver, false).expect("IO Error");
+
+                let mut budget = Budget::new();
+                budget.off();
+                match solver.solve_limited(&budget, &[]) {
+                    SolveRes::SAT(_, _) => panic!("Model for unsatisfiable seed {}", seed),
+                    SolveRes::UnSAT(_) | SolveRes::Interrupted(_, _) => {}
+                }
+            }
+        }
+    }
+}
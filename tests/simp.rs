@@ -0,0 +1,68 @@
+use minisat_rust::sat::Stats;
+
+pub mod common;
+use common::*;
+
+
+#[test]
+fn default_simplification() {
+    check_simp(|_| {});
+}
+
+#[test]
+fn equivalent_literals() {
+    let stats = check_simp(|s| {
+        s.simp.use_equiv = true;
+    });
+    assert!(stats.iter().any(|s| s.equiv_vars > 0), "No equivalent literals substituted");
+}
+
+#[test]
+fn blocked_clauses() {
+    let stats = check_simp(|s| {
+        s.simp.use_bce = true;
+    });
+    assert!(stats.iter().any(|s| s.blocked_clauses > 0), "No blocked clauses removed");
+}
+
+#[test]
+fn covered_asymmetric_blocked_clauses() {
+    let blocked = |stats: Vec<Stats>| stats.iter().map(|s| s.blocked_clauses).sum::<u64>();
+    let plain = blocked(check_simp(|s| {
+        s.simp.use_bce = true;
+    }));
+    let extended = blocked(check_simp(|s| {
+        s.simp.use_bce = true;
+        s.simp.use_cce = true;
+        s.simp.use_abce = true;
+    }));
+    assert!(extended > plain, "No covered or asymmetric blocked clauses removed ({} vs {})", extended, plain);
+}
+
+#[test]
+fn bounded_variable_addition() {
+    check_simp(|s| {
+        s.simp.use_bva = true;
+    });
+
+    // The pairwise at-most-one constraints are what BVA compresses:
+    let sizes = [(5, 5), (6, 5), (7, 7), (8, 7)];
+    let stats = check_pigeonholes(|s| s.simp.use_bva = true, &sizes);
+    for (s, (pigeons, holes)) in stats.iter().zip(sizes.iter()) {
+        assert!(s.added_vars > 0, "No variables added for pigeonhole {} {}", pigeons, holes);
+    }
+}
+
+
+#[test]
+fn gate_based_elimination() {
+    check_simp(|s| {
+        s.simp.use_gates = true;
+    });
+
+    let stats = check_simp_on(circuit_cnf, |s| {
+        s.simp.use_gates = true;
+    });
+    assert!(stats.iter().all(|s| s.gates <= s.eliminated_vars));
+    assert!(stats.iter().any(|s| s.gates > 0), "No gate definitions used");
+}
@@ -0,0 +1,61 @@
+use minisat_rust::{solve_with, MainOptions, Mode};
+use minisat_rust::sat::minisat;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+
+pub mod common;
+use common::*;
+
+
+#[test]
+fn stats_json() {
+    let cnf = pigeonhole_cnf(7, 6);
+    let mut input = tempfile::NamedTempFile::new().unwrap();
+    input.write_all(cnf.as_bytes()).unwrap();
+    let output = tempfile::NamedTempFile::new().unwrap();
+
+    let options = MainOptions {
+        mode: Mode::Solve,
+        strict: false,
+        pre: true,
+        solve: true,
+        in_path: input.path().to_path_buf(),
+        out_path: None,
+        dimacs_path: None,
+        stats_json: Some(output.path().to_path_buf()),
+        checkpoint: None,
+        resume: None,
+    };
+    solve_with(minisat::SimpSolver::new(Default::default()), options).unwrap();
+
+    let json = fs::read_to_string(output.path()).unwrap();
+    let lines: Vec<&str> = json.lines().collect();
+    assert_eq!((lines[0], lines[lines.len() - 1]), ("{", "}"));
+    let body = &lines[1..lines.len() - 1];
+    assert!(body[..body.len() - 1].iter().all(|line| line.ends_with(',')) && !body[body.len() - 1].ends_with(','));
+
+    let values: HashMap<&str, &str> =
+        body.iter()
+            .map(|line| {
+                let (key, value) = line.trim().trim_end_matches(',').split_once(": ").expect("Not a member");
+                (key.strip_prefix('"').and_then(|k| k.strip_suffix('"')).expect("Not a key"), value)
+            })
+            .collect();
+    assert_eq!(values.len(), body.len(), "Duplicate keys");
+    let number = |key: &str| -> f64 { values.get(key).unwrap_or_else(|| panic!("No {}", key)).parse().expect("Not a number") };
+
+    let (_, stats) = solve_stats(minisat::SimpSolver::new(Default::default()), &cnf);
+    assert_eq!(number("conflicts"), stats.conflicts as f64);
+    assert_eq!(number("decisions"), stats.decisions as f64);
+    assert_eq!(number("propagations"), stats.propagations as f64);
+    assert_eq!(number("learnt_clauses"), stats.learnts as f64);
+    assert_eq!(number("eliminated_vars"), stats.eliminated_vars as f64);
+    assert_eq!(number("walk_flips"), 0.0);
+    assert!((number("avg_lbd") - stats.avg_lbd()).abs() < 1e-9);
+
+    let times: Vec<f64> = ["parse_time", "simplify_time", "search_time"].iter().map(|key| number(key)).collect();
+    assert!(times.iter().all(|&t| t >= 0.0) && times[2] > 0.0);
+    assert!(times.iter().sum::<f64>() <= number("cpu_time"));
+    assert!(values["memory_mb"] == "null" || number("memory_mb") > 0.0);
+}